@@ -0,0 +1,121 @@
+//! Compares BVH construction and traversal against the previous, median-split builder
+
+use std::sync::Arc;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use lustre::{
+    bounds::BoundingBox,
+    scenes::{get_scene, SceneType},
+    BvhNode, Camera, HitRecord, Hittable, LinearBvh, Ray,
+};
+use rand::{prelude::IteratorRandom, Rng, SeedableRng};
+
+/// The previous BVH builder, splitting at the median along a random axis
+struct MedianBvh {
+    left: Arc<dyn Hittable>,
+    right: Arc<dyn Hittable>,
+    bbox: BoundingBox,
+}
+
+impl MedianBvh {
+    fn new(hitlist: &mut [Arc<dyn Hittable>], rng: &mut impl Rng) -> Self {
+        let (left, right) = match hitlist.len() {
+            1 => (hitlist[0].clone(), hitlist[0].clone()),
+            2 => (hitlist[0].clone(), hitlist[1].clone()),
+            span => {
+                let axis_idx = (0..3).choose(rng).unwrap();
+                hitlist.sort_by(|a, b| {
+                    let a_box = a.bounding_box(0.0, 1.0).unwrap();
+                    let b_box = b.bounding_box(0.0, 1.0).unwrap();
+                    a_box.min[axis_idx].total_cmp(&b_box.min[axis_idx])
+                });
+
+                let (half0, half1) = hitlist.split_at_mut(span / 2);
+                let left: Arc<dyn Hittable> = MedianBvh::new(half0, rng).wrap();
+                let right: Arc<dyn Hittable> = MedianBvh::new(half1, rng).wrap();
+                (left, right)
+            }
+        };
+
+        let bbox = left
+            .bounding_box(0.0, 1.0)
+            .unwrap()
+            .union(&right.bounding_box(0.0, 1.0).unwrap());
+        Self { left, right, bbox }
+    }
+}
+
+impl Hittable for MedianBvh {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return None;
+        }
+
+        let left_hit = self.left.hit(ray, t_min, t_max);
+        let t_max = left_hit.as_ref().map_or(t_max, |rec| rec.t);
+        self.right.hit(ray, t_min, t_max).or(left_hit)
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        Some(self.bbox)
+    }
+}
+
+/// Generates a grid of camera rays covering the image
+fn camera_rays(cam: &Camera, rng: &mut impl Rng) -> Vec<Ray> {
+    let side = 64;
+    (0..side * side)
+        .map(|idx| {
+            let u = (idx % side) as f32 / (side - 1) as f32;
+            let v = (idx / side) as f32 / (side - 1) as f32;
+            cam.get_ray(u, v, rng)
+        })
+        .collect()
+}
+
+/// Finds the closest hit for each of the rays
+fn trace_all(world: &dyn Hittable, rays: &[Ray]) -> usize {
+    rays.iter()
+        .filter(|ray| world.hit(ray, 0.001, f32::INFINITY).is_some())
+        .count()
+}
+
+fn bench_bvh(c: &mut Criterion) {
+    for scene in [SceneType::CoverPhoto, SceneType::FinalScene] {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+        let (cam, world, _) = get_scene(400, scene, &mut rng);
+        let rays = camera_rays(&cam, &mut rng);
+        let name = format!("{:?}", scene);
+
+        let mut build = c.benchmark_group("bvh_build");
+        build.bench_function(BenchmarkId::new("median", &name), |b| {
+            b.iter(|| MedianBvh::new(&mut world.clone(), &mut rng))
+        });
+        build.bench_function(BenchmarkId::new("sah", &name), |b| {
+            b.iter(|| BvhNode::new(black_box(world.clone()), 0.0, 1.0).unwrap())
+        });
+        build.bench_function(BenchmarkId::new("linear", &name), |b| {
+            b.iter(|| LinearBvh::new(black_box(world.clone()), 0.0, 1.0).unwrap())
+        });
+        build.finish();
+
+        let median = MedianBvh::new(&mut world.clone(), &mut rng);
+        let sah = BvhNode::new(world.clone(), 0.0, 1.0).unwrap();
+        let linear = LinearBvh::new(world.clone(), 0.0, 1.0).unwrap();
+
+        let mut traverse = c.benchmark_group("bvh_traversal");
+        traverse.bench_function(BenchmarkId::new("median", &name), |b| {
+            b.iter(|| trace_all(&median, black_box(&rays)))
+        });
+        traverse.bench_function(BenchmarkId::new("sah", &name), |b| {
+            b.iter(|| trace_all(&sah, black_box(&rays)))
+        });
+        traverse.bench_function(BenchmarkId::new("linear", &name), |b| {
+            b.iter(|| trace_all(&linear, black_box(&rays)))
+        });
+        traverse.finish();
+    }
+}
+
+criterion_group!(benches, bench_bvh);
+criterion_main!(benches);
@@ -21,9 +21,37 @@ impl BoundingBox {
     ///
     /// Checks for slab intersection in each of the 3 dimensions.
     pub fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
-        let inverse_dir = ray.direction.recip();
-        let diff0 = self.min - ray.origin;
-        let diff1 = self.max - ray.origin;
+        self.hit_with_inverse(ray.origin, ray.direction.recip(), t_min, t_max)
+    }
+
+    /// Same as [BoundingBox::hit], for a ray given by its origin and its precomputed inverse direction.
+    ///
+    /// Useful when testing one ray against many boxes.
+    pub fn hit_with_inverse(
+        &self,
+        origin: Vec3A,
+        inverse_dir: Vec3A,
+        t_min: f32,
+        t_max: f32,
+    ) -> bool {
+        self.clip_with_inverse(origin, inverse_dir, t_min, t_max)
+            .is_some()
+    }
+
+    /// Returns the part of the `t_min..t_max` interval along the ray that is inside this bounding box
+    pub fn clip(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<(f32, f32)> {
+        self.clip_with_inverse(ray.origin, ray.direction.recip(), t_min, t_max)
+    }
+
+    fn clip_with_inverse(
+        &self,
+        origin: Vec3A,
+        inverse_dir: Vec3A,
+        mut t_min: f32,
+        mut t_max: f32,
+    ) -> Option<(f32, f32)> {
+        let diff0 = self.min - origin;
+        let diff1 = self.max - origin;
 
         // Check for slab intersection in each dimension
         for axis_idx in 0..3 {
@@ -38,14 +66,15 @@ impl BoundingBox {
                 (t0, t1)
             };
 
-            let t_near = t0.max(t_min);
-            let t_far = t1.min(t_max);
-            if t_far <= t_near {
-                return false;
+            // narrow the interval, so the slabs must overlap each other
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
+            if t_max <= t_min {
+                return None;
             }
         }
 
-        true
+        Some((t_min, t_max))
     }
 
     /// Returns a bounding box enclosing this and the other box.
@@ -58,6 +87,37 @@ impl BoundingBox {
         let max = self.max.max(other.max);
         Self { min, max }
     }
+
+    /// Returns the bounding box shared by this and the other box.
+    ///
+    /// Boxes that don't overlap give an empty box at the corner of their gap.
+    pub fn intersection(&self, other: &BoundingBox) -> BoundingBox {
+        let min = self.min.max(other.min);
+        let max = self.max.min(other.max).max(min);
+        Self { min, max }
+    }
+
+    /// Returns the eight corners of this bounding box
+    pub fn corners(&self) -> [Vec3A; 8] {
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|corner| {
+            Vec3A::select(
+                glam::BVec3A::new(corner & 1 == 0, corner & 2 == 0, corner & 4 == 0),
+                self.min,
+                self.max,
+            )
+        })
+    }
+
+    /// Returns the point in the center of this bounding box
+    pub fn centroid(&self) -> Vec3A {
+        0.5 * (self.min + self.max)
+    }
+
+    /// Returns the total area of the six faces of this bounding box
+    pub fn surface_area(&self) -> f32 {
+        let size = self.max - self.min;
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
 }
 
 impl Default for BoundingBox {
@@ -1,15 +1,25 @@
 //! Bounding Volume Hierarchy
 
-use std::{cmp::Ordering, fmt::Debug, sync::Arc};
+use std::{
+    fmt::{Debug, Display},
+    sync::Arc,
+};
 
-use rand::{prelude::IteratorRandom, Rng};
+use glam::Vec3A;
 
 use crate::{
     bounds::BoundingBox,
-    hittables::{HitRecord, Hittable, HittableList},
+    hittables::{gather_lights, HitRecord, Hittable, HittableList},
     ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
 };
 
+pub mod linear;
+pub use linear::*;
+
+/// Number of bins candidate splits are evaluated at, per axis
+const BIN_COUNT: usize = 12;
+
 /// A node in the BVH.
 ///
 /// Holds the bounding box that contains the two [Hittable] children
@@ -19,75 +29,219 @@ pub struct BvhNode {
     bbox: BoundingBox,
 }
 
-/// Compares two bounding boxes based on existence and then along the given axis
-fn box_cmp(a: &Option<BoundingBox>, b: &Option<BoundingBox>, axis_idx: usize) -> Ordering {
-    match (a, b) {
-        (None, None) => {
-            panic!("box_cmp encountered two unbounded objects");
-        }
-        (None, Some(_)) => Ordering::Less,
-        (Some(_), None) => Ordering::Greater,
-        (Some(a_box), Some(b_box)) => a_box.min[axis_idx]
-            .partial_cmp(&b_box.min[axis_idx])
-            .expect("boxes contained extreme FP values"),
+/// An object to be placed into the BVH, along with its precomputed bounds
+struct BuildItem {
+    object: Arc<dyn Hittable>,
+    bbox: BoundingBox,
+    centroid: Vec3A,
+}
+
+impl BuildItem {
+    /// Precomputes the bounds of each object.
+    ///
+    /// Objects without a bounding box are returned separately.
+    fn from_list(hitlist: HittableList, time0: f32, time1: f32) -> (Vec<Self>, HittableList) {
+        let mut items = vec![];
+        let mut unbounded = vec![];
+        for object in hitlist {
+            match object.bounding_box(time0, time1) {
+                Some(bbox) => items.push(BuildItem {
+                    object,
+                    bbox,
+                    centroid: bbox.centroid(),
+                }),
+                None => unbounded.push(object),
+            }
+        }
+        (items, unbounded)
     }
 }
 
-impl BvhNode {
-    /// Creates a new BvhNode
-    pub fn new(mut hitlist: HittableList, time0: f32, time1: f32, rng: &mut impl Rng) -> Self {
-        BvhNode::new_node(&mut hitlist[..], time0, time1, rng)
+/// Errors possible when building a BVH
+#[derive(Debug)]
+pub enum BvhError {
+    /// There are no objects to build the BVH from
+    Empty,
+    /// An object has no bounding box, which a [BvhNode] cannot hold
+    Unbounded,
+}
+
+impl Display for BvhError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BvhError::Empty => write!(f, "a BVH needs at least one object"),
+            BvhError::Unbounded => write!(f, "a BvhNode cannot hold unbounded objects"),
+        }
     }
+}
+
+impl std::error::Error for BvhError {}
+
+/// The result of partitioning [BuildItem]s into two groups
+struct Split {
+    /// Index where the second group starts
+    mid: usize,
+    /// Axis the items were sorted along
+    axis: usize,
+    /// SAH cost of the split, if it was chosen by cost
+    cost: Option<f32>,
+}
 
-    /// Implementation of `new`
-    fn new_node(
-        hitlist: &mut [Arc<dyn Hittable>],
-        time0: f32,
-        time1: f32,
-        rng: &mut impl Rng,
-    ) -> Self {
-        if hitlist.is_empty() {
-            panic!("Given empty scene!");
+/// A bin of objects used while evaluating candidate splits
+#[derive(Clone, Copy, Default)]
+struct Bin {
+    bbox: Option<BoundingBox>,
+    count: usize,
+}
+
+/// Combines two optional bounding boxes
+fn union(a: Option<BoundingBox>, b: &BoundingBox) -> Option<BoundingBox> {
+    Some(a.map_or(*b, |a| a.union(b)))
+}
+
+/// Returns the bin the centroid falls into along the given axis
+fn bin_index(centroid: Vec3A, centroid_min: Vec3A, extent: Vec3A, axis_idx: usize) -> usize {
+    let offset = (centroid[axis_idx] - centroid_min[axis_idx]) / extent[axis_idx];
+    ((offset * BIN_COUNT as f32) as usize).min(BIN_COUNT - 1)
+}
+
+/// Reorders the items into two groups.
+///
+/// The split is chosen by binning the item centroids along each axis,
+/// and picking the bin boundary minimizing the SAH cost.
+/// If no split is better than another, e.g. for items sharing a centroid,
+/// the items are split in half along the longest axis instead.
+fn partition(items: &mut [BuildItem]) -> Split {
+    let centroid_min = items.iter().fold(Vec3A::splat(f32::INFINITY), |acc, item| {
+        acc.min(item.centroid)
+    });
+    let centroid_max = items
+        .iter()
+        .fold(Vec3A::splat(f32::NEG_INFINITY), |acc, item| {
+            acc.max(item.centroid)
+        });
+    let extent = centroid_max - centroid_min;
+
+    // (cost, axis, split bin)
+    let mut best: Option<(f32, usize, usize)> = None;
+
+    for axis_idx in 0..3 {
+        if extent[axis_idx] <= f32::EPSILON {
+            continue;
         }
 
-        let span = hitlist.len();
-        let start = 0;
+        let bin_of = |item: &BuildItem| bin_index(item.centroid, centroid_min, extent, axis_idx);
 
-        let (left, right) = match span {
-            1 => (hitlist[start].clone(), hitlist[start].clone()),
-            2 => (hitlist[start].clone(), hitlist[start + 1].clone()),
-            _ => {
-                // TODO implement better axis decision-making
-                let axis_idx = (0..3).choose(rng).unwrap();
+        let mut bins = [Bin::default(); BIN_COUNT];
+        for item in items.iter() {
+            let bin = &mut bins[bin_of(item)];
+            bin.bbox = union(bin.bbox, &item.bbox);
+            bin.count += 1;
+        }
 
-                hitlist.sort_by(|a, b| {
-                    box_cmp(
-                        &a.bounding_box(time0, time1),
-                        &b.bounding_box(time0, time1),
-                        axis_idx,
-                    )
-                });
+        // sweep from the right, storing the area and count right of each split
+        let mut right_costs = [0.0; BIN_COUNT];
+        let mut right_box = None;
+        let mut right_count = 0;
+        for split in (1..BIN_COUNT).rev() {
+            if let Some(bbox) = &bins[split].bbox {
+                right_box = union(right_box, bbox);
+            }
+            right_count += bins[split].count;
+            right_costs[split] = right_box.map_or(0.0, |b| b.surface_area()) * right_count as f32;
+        }
 
-                let (half0, half1) = hitlist.split_at_mut(span / 2);
+        // sweep from the left, combining both sides into the cost of each split
+        let mut left_box = None;
+        let mut left_count = 0;
+        for split in 1..BIN_COUNT {
+            if let Some(bbox) = &bins[split - 1].bbox {
+                left_box = union(left_box, bbox);
+            }
+            left_count += bins[split - 1].count;
+            if left_count == 0 || left_count == items.len() {
+                continue;
+            }
 
-                let left: Arc<dyn Hittable> = BvhNode::new_node(half0, time0, time1, rng).wrap();
-                let right: Arc<dyn Hittable> = BvhNode::new_node(half1, time0, time1, rng).wrap();
-                (left, right)
+            let cost =
+                left_box.map_or(0.0, |b| b.surface_area()) * left_count as f32 + right_costs[split];
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis_idx, split));
             }
-        };
+        }
+    }
 
-        let bbox = match (
-            left.bounding_box(time0, time1),
-            right.bounding_box(time0, time1),
-        ) {
-            (None, None) => {
-                panic!("new_node encountered two unbounded objects");
+    match best {
+        Some((cost, axis_idx, split)) => {
+            // the sort is stable, keeping the build deterministic
+            items.sort_by(|a, b| a.centroid[axis_idx].total_cmp(&b.centroid[axis_idx]));
+            let mid = items.partition_point(|item| {
+                bin_index(item.centroid, centroid_min, extent, axis_idx) < split
+            });
+            Split {
+                mid,
+                axis: axis_idx,
+                cost: Some(cost),
+            }
+        }
+        None => {
+            let bounds = items
+                .iter()
+                .fold(items[0].bbox, |acc, item| acc.union(&item.bbox));
+            let size = bounds.max - bounds.min;
+            let axis_idx = if size.x >= size.y && size.x >= size.z {
+                0
+            } else if size.y >= size.z {
+                1
+            } else {
+                2
+            };
+            items.sort_by(|a, b| a.centroid[axis_idx].total_cmp(&b.centroid[axis_idx]));
+            Split {
+                mid: items.len() / 2,
+                axis: axis_idx,
+                cost: None,
+            }
+        }
+    }
+}
+
+impl BvhNode {
+    /// Creates a new BvhNode
+    ///
+    /// The hierarchy is built using the surface area heuristic (SAH),
+    /// so the same list of objects always results in the same tree.
+    /// All objects need a bounding box, see [LinearBvh] for a BVH that also holds unbounded objects.
+    pub fn new(hitlist: HittableList, time0: f32, time1: f32) -> Result<Self, BvhError> {
+        let (mut items, unbounded) = BuildItem::from_list(hitlist, time0, time1);
+        if !unbounded.is_empty() {
+            return Err(BvhError::Unbounded);
+        }
+        if items.is_empty() {
+            return Err(BvhError::Empty);
+        }
+        Ok(BvhNode::new_node(&mut items[..]))
+    }
+
+    /// Implementation of `new`, for a non-empty list of items
+    fn new_node(items: &mut [BuildItem]) -> Self {
+        let (left, right) = match items.len() {
+            1 => (items[0].object.clone(), items[0].object.clone()),
+            2 => (items[0].object.clone(), items[1].object.clone()),
+            _ => {
+                let mid = partition(items).mid;
+                let (half0, half1) = items.split_at_mut(mid);
+
+                let left: Arc<dyn Hittable> = BvhNode::new_node(half0).wrap();
+                let right: Arc<dyn Hittable> = BvhNode::new_node(half1).wrap();
+                (left, right)
             }
-            (None, Some(b)) => b,
-            (Some(a), None) => a,
-            (Some(a), Some(b)) => a.union(&b),
         };
 
+        let bbox = items
+            .iter()
+            .fold(items[0].bbox, |acc, item| acc.union(&item.bbox));
+
         Self { left, right, bbox }
     }
 }
@@ -98,6 +252,18 @@ impl Debug for BvhNode {
     }
 }
 
+impl BvhNode {
+    /// Returns the children of the node
+    fn children(&self) -> Vec<&Arc<dyn Hittable>> {
+        // single-object nodes hold the same object on both sides
+        if Arc::ptr_eq(&self.left, &self.right) {
+            vec![&self.left]
+        } else {
+            vec![&self.left, &self.right]
+        }
+    }
+}
+
 impl Hittable for BvhNode {
     fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
         if self.bbox.hit(ray, t_min, t_max) {
@@ -129,4 +295,113 @@ impl Hittable for BvhNode {
     fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
         Some(self.bbox)
     }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        gather_lights(self.children(), lights);
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        let children = self.children();
+
+        // flatten child nodes into a single list of objects
+        let mut objects = vec![];
+        for child in children {
+            match child.describe(materials)? {
+                ObjectDescription::Bvh { objects: nested } => objects.extend(nested),
+                desc => objects.push(desc),
+            }
+        }
+
+        Some(ObjectDescription::Bvh { objects })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3A;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::{hittables::Sphere, material::Material, textures::SolidColor};
+
+    #[test]
+    fn matches_linear_search() {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+        let mat = Arc::new(Material::Lambertian {
+            albedo: Arc::new(SolidColor::new(Vec3A::ONE)),
+        });
+        let spheres: HittableList = (0..200)
+            .map(|_| -> Arc<dyn Hittable> {
+                Sphere::new(rng.gen::<Vec3A>() * 10.0, rng.gen_range(0.1..0.5), &mat).wrap()
+            })
+            .collect();
+        let bvh = BvhNode::new(spheres.clone(), 0.0, 1.0).unwrap();
+        let linear = LinearBvh::new(spheres.clone(), 0.0, 1.0).unwrap();
+
+        for _ in 0..500 {
+            let ray = Ray::new(
+                Vec3A::splat(-5.0),
+                rng.gen::<Vec3A>() + Vec3A::splat(0.1),
+                0.0,
+            );
+            let expected = spheres.hit(&ray, 0.001, f32::INFINITY).map(|rec| rec.t);
+            let actual = bvh.hit(&ray, 0.001, f32::INFINITY).map(|rec| rec.t);
+            assert_eq!(expected, actual);
+            let actual = linear.hit(&ray, 0.001, f32::INFINITY).map(|rec| rec.t);
+            assert_eq!(expected, actual);
+        }
+    }
+
+    /// An infinite plane at y = 0, without a bounding box
+    struct Floor(Arc<Material>);
+
+    impl Hittable for Floor {
+        fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+            let t = -ray.origin.y / ray.direction.y;
+            (t_min..t_max).contains(&t).then(|| HitRecord {
+                point: ray.at(t),
+                normal: Vec3A::Y,
+                material: Arc::clone(&self.0),
+                t,
+                u: 0.0,
+                v: 0.0,
+                front_face: true,
+            })
+        }
+
+        fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+            None
+        }
+    }
+
+    #[test]
+    fn unbounded_objects() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let objects: HittableList = vec![
+            Floor(Arc::clone(&mat)).wrap(),
+            Sphere::new(Vec3A::new(0.0, 1.0, 0.0), 0.5, &mat).wrap(),
+        ];
+
+        assert!(matches!(
+            BvhNode::new(objects.clone(), 0.0, 1.0),
+            Err(BvhError::Unbounded)
+        ));
+        assert!(matches!(
+            LinearBvh::new(vec![], 0.0, 1.0),
+            Err(BvhError::Empty)
+        ));
+
+        let linear = LinearBvh::new(objects, 0.0, 1.0).unwrap();
+        assert!(linear.bounding_box(0.0, 1.0).is_none());
+
+        // the sphere is in front of the floor
+        let ray = Ray::new(Vec3A::new(0.0, 5.0, 0.0), -Vec3A::Y, 0.0);
+        let rec = linear.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 3.5).abs() < 1e-4);
+
+        // only the floor is hit
+        let ray = Ray::new(Vec3A::new(5.0, 5.0, 0.0), -Vec3A::Y, 0.0);
+        let rec = linear.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-4);
+    }
 }
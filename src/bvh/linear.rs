@@ -0,0 +1,213 @@
+//! Flattened Bounding Volume Hierarchy
+
+use std::{fmt::Debug, sync::Arc};
+
+use crate::{
+    bounds::BoundingBox,
+    hittables::{gather_lights, HitRecord, Hittable, HittableList},
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+};
+
+use super::{partition, BuildItem, BvhError};
+
+/// Maximum number of objects placed into a single leaf by cost
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Maximum depth of the hierarchy, and the size of the traversal stack
+const MAX_DEPTH: usize = 64;
+
+/// Cost of traversing a node, relative to the cost of hitting an object
+const TRAVERSAL_COST: f32 = 0.125;
+
+/// A node in a [LinearBvh]
+#[derive(Debug, Clone, Copy)]
+struct LinearNode {
+    bbox: BoundingBox,
+    /// For leaves, the index of the first object.
+    /// For interior nodes, the index of the second child, as the first child follows its parent.
+    offset: u32,
+    /// Number of objects in a leaf, 0 for interior nodes
+    count: u32,
+    /// Axis the children of an interior node were split along
+    axis: u8,
+}
+
+/// A BVH stored as an array of nodes, in depth-first order.
+///
+/// Compared to [BvhNode](super::BvhNode), traversal doesn't chase pointers between nodes,
+/// and visits the child nearer to the ray first.
+///
+/// Objects without a bounding box, such as infinite planes, are kept outside of the hierarchy
+/// and tested against every ray.
+pub struct LinearBvh {
+    nodes: Vec<LinearNode>,
+    /// The objects, ordered such that each leaf's objects are contiguous
+    objects: HittableList,
+    /// The objects without a bounding box
+    unbounded: HittableList,
+}
+
+impl LinearBvh {
+    /// Creates a new LinearBvh
+    ///
+    /// The hierarchy is built using the surface area heuristic (SAH),
+    /// so the same list of objects always results in the same hierarchy.
+    pub fn new(hitlist: HittableList, time0: f32, time1: f32) -> Result<Self, BvhError> {
+        let (mut items, unbounded) = BuildItem::from_list(hitlist, time0, time1);
+        if items.is_empty() && unbounded.is_empty() {
+            return Err(BvhError::Empty);
+        }
+
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * items.len()),
+            objects: Vec::with_capacity(items.len()),
+            unbounded,
+        };
+        if !items.is_empty() {
+            bvh.build(&mut items[..], 0);
+        }
+        Ok(bvh)
+    }
+
+    /// Adds the nodes for the given items, returning the index of the topmost one
+    fn build(&mut self, items: &mut [BuildItem], depth: usize) -> usize {
+        let bbox = items
+            .iter()
+            .fold(items[0].bbox, |acc, item| acc.union(&item.bbox));
+
+        let node_idx = self.nodes.len();
+        self.nodes.push(LinearNode {
+            bbox,
+            offset: self.objects.len() as u32,
+            count: items.len() as u32,
+            axis: 0,
+        });
+
+        if items.len() == 1 || depth + 1 >= MAX_DEPTH {
+            self.objects
+                .extend(items.iter().map(|item| Arc::clone(&item.object)));
+            return node_idx;
+        }
+
+        // only split if it's expected to be cheaper than hitting every object
+        let split = partition(items);
+        let split_cost = split
+            .cost
+            .map(|cost| TRAVERSAL_COST + cost / bbox.surface_area());
+        if items.len() <= MAX_LEAF_SIZE && split_cost.is_none_or(|cost| cost >= items.len() as f32)
+        {
+            self.objects
+                .extend(items.iter().map(|item| Arc::clone(&item.object)));
+            return node_idx;
+        }
+
+        let (half0, half1) = items.split_at_mut(split.mid);
+        self.build(half0, depth + 1);
+        let second_idx = self.build(half1, depth + 1);
+
+        let node = &mut self.nodes[node_idx];
+        node.offset = second_idx as u32;
+        node.count = 0;
+        node.axis = split.axis as u8;
+        node_idx
+    }
+}
+
+impl Debug for LinearBvh {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "LinearBvh {{{:?}, {} nodes, {} unbounded}}",
+            self.nodes.first().map(|node| node.bbox),
+            self.nodes.len(),
+            self.unbounded.len()
+        )
+    }
+}
+
+impl Hittable for LinearBvh {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let inverse_dir = ray.direction.recip();
+
+        let mut closest = None;
+        let mut t_closest = t_max;
+
+        for object in self.unbounded.iter() {
+            if let Some(rec) = object.hit(ray, t_min, t_closest) {
+                t_closest = rec.t;
+                closest = Some(rec);
+            }
+        }
+
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        // indices of nodes left to visit
+        let mut stack = [0; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut node_idx = 0;
+
+        loop {
+            let node = &self.nodes[node_idx];
+            if node
+                .bbox
+                .hit_with_inverse(ray.origin, inverse_dir, t_min, t_closest)
+            {
+                if node.count > 0 {
+                    let start = node.offset as usize;
+                    for object in &self.objects[start..start + node.count as usize] {
+                        if let Some(rec) = object.hit(ray, t_min, t_closest) {
+                            t_closest = rec.t;
+                            closest = Some(rec);
+                        }
+                    }
+                } else {
+                    // visit the nearer child first, as hits there can rule out the other child
+                    let (near, far) = if inverse_dir[node.axis as usize] < 0.0 {
+                        (node.offset as usize, node_idx + 1)
+                    } else {
+                        (node_idx + 1, node.offset as usize)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    node_idx = near;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            node_idx = stack[stack_len];
+        }
+
+        closest
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        self.nodes.first().map(|node| node.bbox)
+    }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        gather_lights(self.objects.iter().chain(self.unbounded.iter()), lights);
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        // flatten nested hierarchies into a single list of objects
+        let mut objects = vec![];
+        for object in self.objects.iter().chain(self.unbounded.iter()) {
+            match object.describe(materials)? {
+                ObjectDescription::Bvh { objects: nested } => objects.extend(nested),
+                desc => objects.push(desc),
+            }
+        }
+
+        Some(ObjectDescription::Bvh { objects })
+    }
+}
@@ -10,7 +10,9 @@ use std::ops::Range;
 use glam::Vec3A;
 use rand::Rng;
 
-use crate::{color::Color, ray::Ray, utils::random::rand_vec3_in_unit_disk};
+use crate::{
+    color::Color, ray::Ray, scenes::CameraDescription, utils::random::rand_vec3_in_unit_disk,
+};
 
 /// A Camera that generates rays
 #[derive(Debug)]
@@ -36,6 +38,8 @@ pub struct Camera {
     shutter_time: Range<f32>,
     /// Background color
     pub bg_color: Color,
+    /// Properties the camera was created with
+    description: CameraDescription,
 }
 
 impl Camera {
@@ -49,6 +53,7 @@ impl Camera {
     /// * aspect_ratio - The aspect ratio of the viewport
     /// * aperture - How "big" the approximated lens is
     /// * focus_dist - The distance to the plane in space where objects are "in focus"
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         look_from: Vec3A,
         look_at: Vec3A,
@@ -75,6 +80,18 @@ impl Camera {
         let vertical = viewport_h * focus_dist * v;
         let ll_corner = origin - horizontal / 2.0 - vertical / 2.0 - focus_dist * w;
 
+        let description = CameraDescription {
+            look_from,
+            look_at,
+            view_up,
+            vert_fov,
+            aspect_ratio,
+            aperture,
+            focus_dist,
+            shutter_time: shutter_time.clone(),
+            bg_color,
+        };
+
         let lens_radius = aperture / 2.0;
         Self {
             origin,
@@ -87,9 +104,15 @@ impl Camera {
             lens_radius,
             shutter_time,
             bg_color,
+            description,
         }
     }
 
+    /// Returns the properties the camera was created with
+    pub fn description(&self) -> &CameraDescription {
+        &self.description
+    }
+
     /// Returns a ray from the camera for the normalized pixel (u,v)
     pub fn get_ray(&self, u: f32, v: f32, rng: &mut impl Rng) -> Ray {
         let rd = self.lens_radius * rand_vec3_in_unit_disk(rng);
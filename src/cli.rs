@@ -3,13 +3,19 @@
 // pub is neeeded for the program to called Arguments::parse()
 pub use clap::Parser;
 
-use crate::scenes::SceneType;
+use lustre::{
+    integrator::IntegratorType,
+    render::{AovType, ToneMapper},
+    scenes::SceneType,
+};
 
 /// Argument defintions for [clap::Parser]
 #[derive(Parser, Debug)]
 #[clap(version, about, global_setting(clap::AppSettings::DeriveDisplayOrder))]
 pub struct Arguments {
     /// The path to the file to write the resulting image into
+    ///
+    /// `.exr` and `.hdr` files receive the unclamped linear radiance of each pixel
     #[clap(
         short,
         long,
@@ -43,9 +49,135 @@ pub struct Arguments {
     )]
     pub bounce_depth: u16,
 
+    /// Sample pixels adaptively, stopping once their relative noise falls below THRESHOLD
+    ///
+    /// The noise of a pixel is the half-width of the 95% confidence interval of its luminance,
+    /// relative to the luminance itself. The samples per pixel become the most spent on any pixel
+    #[clap(long, value_parser, value_name = "THRESHOLD")]
+    pub adaptive: Option<f32>,
+
+    /// number of samples taken for a pixel between judging its noise, with adaptive sampling
+    #[clap(
+        long,
+        value_parser = valid_count::<u32>,
+        default_value_t = 16,
+        value_name = "NUM"
+    )]
+    pub adaptive_batch: u32,
+
+    /// Write a heatmap of the samples spent on each pixel next to the image, as `output.samples.png`
+    #[clap(long)]
+    pub sample_heatmap: bool,
+
+    /// Render progressively, writing the image so far every NUM sample passes
+    ///
+    /// Each pass takes a sample of every pixel, or a batch of samples with adaptive sampling.
+    /// The image can be kept as is by stopping the render early
+    #[clap(
+        long,
+        value_parser = valid_count::<u32>,
+        value_name = "NUM"
+    )]
+    pub snapshot_passes: Option<u32>,
+
+    /// Render progressively, writing the image so far every SECONDS of rendering
+    ///
+    /// Can be combined with `--snapshot-passes`, writing whenever either is due
+    #[clap(long, value_parser = valid_seconds, value_name = "SECONDS")]
+    pub snapshot_interval: Option<std::time::Duration>,
+
+    /// number of bounces before paths may be terminated early
+    ///
+    /// Past this many bounces, paths carrying little light are randomly stopped by Russian roulette
+    #[clap(long, value_parser, default_value_t = 5, value_name = "NUM")]
+    pub roulette_depth: u16,
+
+    /// How the color seen along each ray is computed
+    ///
+    /// Other integrators than `path` are faster, for checking scenes without full global illumination
+    #[clap(short, long, value_enum, default_value_t = IntegratorType::Path)]
+    pub integrator: IntegratorType,
+
+    /// number of directions sampled per ray by the `ambient-occlusion` integrator
+    #[clap(
+        long,
+        value_parser = valid_count::<u32>,
+        default_value_t = 16,
+        value_name = "NUM"
+    )]
+    pub ao_samples: u32,
+
+    /// distance within which objects occlude each other for the `ambient-occlusion` integrator
+    ///
+    /// Defaults to a tenth of the size of the scene
+    #[clap(long, value_parser, value_name = "DIST")]
+    pub ao_distance: Option<f32>,
+
+    /// How radiance is compressed into the displayable range of 8-bit images
+    #[clap(long = "tone-map", value_enum, default_value_t = ToneMapper::Clamp)]
+    pub tone_mapper: ToneMapper,
+
+    /// exposure value, in stops, applied before tone mapping 8-bit images
+    ///
+    /// Each stop doubles the brightness of the image, negative values darken it
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = 0.0,
+        allow_hyphen_values = true,
+        value_name = "EV"
+    )]
+    pub exposure: f32,
+
+    /// radiance mapped to white by the `extended-reinhard` and `uncharted2` tone mappers
+    ///
+    /// Defaults to the brightest pixel for `extended-reinhard`, and 11.2 for `uncharted2`
+    #[clap(long, value_parser, value_name = "RADIANCE")]
+    pub white_point: Option<f32>,
+
+    /// Denoise the image, guided by the albedo, normals and depth of the surfaces seen
+    ///
+    /// Smooths out the noise of renders with few samples, at the cost of some detail
+    #[clap(long)]
+    pub denoise: bool,
+
+    /// number of denoising passes, each one blurring twice as far as the previous
+    #[clap(
+        long,
+        value_parser = valid_count::<u32>,
+        default_value_t = 5,
+        value_name = "NUM"
+    )]
+    pub denoise_iterations: u32,
+
+    /// Auxiliary passes to write next to the image, such as `output.albedo.png`
+    ///
+    /// Passes are captured from the first surface seen through each pixel.
+    /// They are written in the format of the image, with raw values for `.exr` and `.hdr` files
+    #[clap(long = "aov", value_enum, value_delimiter = ',', value_name = "AOV")]
+    pub aovs: Vec<AovType>,
+
     /// The hardcoded scene to use
     #[clap(short, long, value_enum, default_value_t = SceneType::CoverPhoto)]
     pub scene: SceneType,
+
+    /// The path to a scene description file to use instead of a hardcoded scene
+    ///
+    /// The file format is determined by its extension, either `.ron` or `.json`
+    #[clap(
+        short = 'f',
+        long,
+        value_parser,
+        value_name = "FILE",
+        conflicts_with = "scene"
+    )]
+    pub scene_file: Option<std::path::PathBuf>,
+
+    /// The path to write a description of the scene into, instead of rendering it
+    ///
+    /// The file format is determined by its extension, either `.ron` or `.json`
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub dump_scene: Option<std::path::PathBuf>,
 }
 
 fn valid_count<T>(s: &str) -> Result<T, String>
@@ -65,6 +197,14 @@ where
     }
 }
 
+fn valid_seconds(s: &str) -> Result<std::time::Duration, String> {
+    let seconds = s.parse::<f32>().map_err(|e| e.to_string())?;
+    match std::time::Duration::try_from_secs_f32(seconds) {
+        Ok(duration) if !duration.is_zero() => Ok(duration),
+        _ => Err("duration must be greater than 0".to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,11 +1,13 @@
 //! Color and pixel output
 
 use glam::Vec3A;
+use serde::{Deserialize, Serialize};
 
 /// A RGB color.
 ///
 /// Holds its value as a [Vec3A]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Color {
     value: Vec3A,
 }
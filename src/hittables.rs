@@ -4,23 +4,67 @@
 use std::sync::Arc;
 
 use glam::Vec3A;
+use rand::RngCore;
 
-use crate::{bounds::BoundingBox, material::Material, ray::Ray};
+use crate::{
+    bounds::BoundingBox,
+    material::Material,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+};
 
+pub mod animated;
+pub mod csg;
+pub mod cylinder;
+pub mod heightfield;
 pub mod list;
+pub mod mesh;
+pub mod plane;
 pub mod quad;
 pub mod quadbox;
+pub mod sdf;
 pub mod sphere;
+pub mod torus;
 pub mod transform;
+pub mod triangle;
 pub mod volume;
 
+pub use animated::*;
+pub use csg::*;
+pub use cylinder::*;
+pub use heightfield::*;
 pub use list::*;
+pub use mesh::*;
+pub use plane::*;
 pub use quad::*;
 pub use quadbox::*;
+pub use sdf::*;
 pub use sphere::*;
+pub use torus::*;
 pub use transform::*;
+pub use triangle::*;
 pub use volume::*;
 
+/// Upper bound on the intersections returned by [Hittable::hit_all]
+const MAX_HITS: usize = 64;
+
+/// Distance stepped past each intersection found by [Hittable::hit_all], so it isn't found again
+const HIT_ALL_EPSILON: f32 = 0.0001;
+
+/// Adds the lights among `objects` to `lights`, looking into objects holding others
+pub(crate) fn gather_lights<'a>(
+    objects: impl IntoIterator<Item = &'a Arc<dyn Hittable>>,
+    lights: &mut HittableList,
+) {
+    for object in objects {
+        if object.is_light() {
+            lights.push(Arc::clone(object));
+        } else {
+            object.collect_lights(lights);
+        }
+    }
+}
+
 /// Defines a set of data returned upon a successful intersection
 #[derive(Debug)]
 pub struct HitRecord {
@@ -76,6 +120,58 @@ pub trait Hittable: Send + Sync {
     /// Returns a `Some(Aabb)` if the object has a bounding box (like spheres), otherwise `None` (like planes)
     fn bounding_box(&self, time0: f32, time1: f32) -> Option<BoundingBox>;
 
+    /// Returns every intersection of the ray with the object between `t_min` and `t_max`, nearest first
+    ///
+    /// For closed objects, `front_face` tells whether the ray enters or leaves the object there.
+    /// The default walks along the ray with repeated calls to [Hittable::hit].
+    fn hit_all(&self, ray: &Ray, t_min: f32, t_max: f32) -> Vec<HitRecord> {
+        let mut hits = vec![];
+        let mut t_min = t_min;
+        while hits.len() < MAX_HITS {
+            match self.hit(ray, t_min, t_max) {
+                Some(rec) => {
+                    t_min = rec.t + HIT_ALL_EPSILON;
+                    hits.push(rec);
+                }
+                None => break,
+            }
+        }
+        hits
+    }
+
+    /// Returns whether the object emits light, and can be sampled by [Hittable::sample_direction]
+    ///
+    /// Lights are gathered by [Hittable::collect_lights], to be sampled directly while shading.
+    fn is_light(&self) -> bool {
+        false
+    }
+
+    /// Adds the lights among the objects held by this one to `lights`
+    ///
+    /// Only objects holding others, like lists and BVHs, have any.
+    fn collect_lights(&self, _lights: &mut HittableList) {}
+
+    /// Returns a direction from `origin` towards a random point on the object
+    ///
+    /// Returns `None` if the object can't be sampled from `origin`, which is the default.
+    fn sample_direction(&self, _origin: Vec3A, _rng: &mut dyn RngCore) -> Option<Vec3A> {
+        None
+    }
+
+    /// Returns the probability density, over solid angle,
+    /// of [Hittable::sample_direction] picking `direction` from `origin`
+    fn pdf_value(&self, _origin: Vec3A, _direction: Vec3A) -> f32 {
+        0.0
+    }
+
+    /// Returns a description of the object, as used by scene files
+    ///
+    /// Materials are named through `materials`.
+    /// Returns `None` if the object can't be described, which is the default.
+    fn describe(&self, _materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        None
+    }
+
     fn wrap(self) -> Arc<Self>
     where
         Self: Sized,
@@ -0,0 +1,267 @@
+//! Hittable Instance whose transform changes over time, for motion blur
+
+use std::sync::Arc;
+
+use glam::{Quat, Vec3, Vec3A};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bounds::BoundingBox,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+};
+
+use super::{HitRecord, Hittable};
+
+/// Number of times sampled between keyframes when bounding an [AnimatedTransform]
+const BBOX_STEPS: usize = 16;
+
+/// The transform of an [AnimatedTransform] at one point in time
+///
+/// The object is scaled, then rotated around an `axis` by `degrees`, then translated.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keyframe {
+    pub time: f32,
+    pub scale: Vec3,
+    pub axis: Vec3,
+    pub degrees: f32,
+    pub translation: Vec3,
+}
+
+impl Default for Keyframe {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            scale: Vec3::ONE,
+            axis: Vec3::Y,
+            degrees: 0.0,
+            translation: Vec3::ZERO,
+        }
+    }
+}
+
+impl Keyframe {
+    /// Creates a keyframe at `time` that does not transform the object
+    pub fn new(time: f32) -> Self {
+        Self {
+            time,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the scaling factor of the keyframe
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the rotation of the keyframe, around an `axis`, of `degrees`
+    pub fn with_rotation(mut self, axis: Vec3, degrees: f32) -> Self {
+        self.axis = axis;
+        self.degrees = degrees;
+        self
+    }
+
+    /// Sets the translation of the keyframe
+    pub fn with_translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+}
+
+/// A hittable whose transform is interpolated between [Keyframe]s, at the time of each ray
+///
+/// Translation and scale are interpolated linearly, and rotation by spherical linear interpolation.
+/// Rotations take the shortest way between keyframes,
+/// so a spin needs keyframes less than 180 degrees apart.
+/// Before the first and after the last keyframe, the object holds still.
+pub struct AnimatedTransform {
+    /// Keyframes in order of time
+    keyframes: Vec<Keyframe>,
+    /// Rotation of each keyframe, with signs chosen so neighbours are on the same hemisphere
+    rotations: Vec<Quat>,
+    object: Arc<dyn Hittable>,
+}
+
+impl AnimatedTransform {
+    /// Creates a new AnimatedTransform moving `o` through the given keyframes
+    ///
+    /// Without any keyframes, the object is not transformed.
+    pub fn new(o: &Arc<dyn Hittable>, mut keyframes: Vec<Keyframe>) -> Self {
+        if keyframes.is_empty() {
+            keyframes.push(Keyframe::default());
+        }
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let mut rotations: Vec<Quat> = Vec::with_capacity(keyframes.len());
+        for keyframe in keyframes.iter() {
+            let rotation =
+                Quat::from_axis_angle(keyframe.axis.normalize(), keyframe.degrees.to_radians());
+            // q and -q are the same rotation, but only the closer one interpolates the short way
+            let rotation = match rotations.last() {
+                Some(previous) if previous.dot(rotation) < 0.0 => -rotation,
+                _ => rotation,
+            };
+            rotations.push(rotation);
+        }
+
+        Self {
+            keyframes,
+            rotations,
+            object: Arc::clone(o),
+        }
+    }
+
+    /// Returns the scale, rotation and translation at the given time
+    fn interpolate(&self, time: f32) -> (Vec3A, Quat, Vec3A) {
+        let parts = |idx: usize| {
+            let keyframe = &self.keyframes[idx];
+            (
+                Vec3A::from(keyframe.scale),
+                self.rotations[idx],
+                Vec3A::from(keyframe.translation),
+            )
+        };
+
+        let next = self.keyframes.partition_point(|k| k.time <= time);
+        if next == 0 {
+            return parts(0);
+        }
+        if next == self.keyframes.len() {
+            return parts(next - 1);
+        }
+
+        let (start, end) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        let ratio = (time - start.time) / (end.time - start.time);
+        let (scale0, rotation0, translation0) = parts(next - 1);
+        let (scale1, rotation1, translation1) = parts(next);
+        (
+            scale0.lerp(scale1, ratio),
+            rotation0.slerp(rotation1, ratio),
+            translation0.lerp(translation1, ratio),
+        )
+    }
+}
+
+impl Hittable for AnimatedTransform {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let (scale, rotation, translation) = self.interpolate(ray.time);
+        let inverse_rotation = rotation.inverse();
+        let transformed_ray = Ray::new(
+            inverse_rotation * (ray.origin - translation) / scale,
+            inverse_rotation * ray.direction / scale,
+            ray.time,
+        );
+
+        // normals are transformed by the inverse-transpose, which undoes the scale instead
+        let rec = self.object.hit(&transformed_ray, t_min, t_max)?;
+        Some(HitRecord {
+            point: rotation * (rec.point * scale) + translation,
+            normal: (rotation * (rec.normal / scale)).normalize(),
+            ..rec
+        })
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<BoundingBox> {
+        let original_box = self.object.bounding_box(time0, time1)?;
+
+        // sample the motion over the interval, including every keyframe within it
+        let mut times = vec![time0];
+        times.extend(
+            self.keyframes
+                .iter()
+                .map(|k| k.time)
+                .filter(|&t| time0 < t && t < time1),
+        );
+        times.push(time1);
+        let samples = times
+            .windows(2)
+            .flat_map(|w| {
+                (0..BBOX_STEPS).map(move |i| w[0] + (w[1] - w[0]) * i as f32 / BBOX_STEPS as f32)
+            })
+            .chain([time1])
+            .map(|time| self.interpolate(time));
+
+        let mut bbox: Option<BoundingBox> = None;
+        let mut previous_rotation: Option<Quat> = None;
+        let mut max_angle: f32 = 0.0;
+        let mut max_scale: f32 = 0.0;
+        for (scale, rotation, translation) in samples {
+            for corner in original_box.corners() {
+                let corner = rotation * (corner * scale) + translation;
+                let corner = BoundingBox::new(corner, corner);
+                bbox = Some(bbox.map_or(corner, |bbox| bbox.union(&corner)));
+            }
+            if let Some(previous) = previous_rotation {
+                max_angle = max_angle.max(previous.angle_between(rotation));
+            }
+            previous_rotation = Some(rotation);
+            max_scale = max_scale.max(scale.abs().max_element());
+        }
+
+        // corners move along arcs between samples, bulging out by at most the sagitta
+        let radius = max_scale * original_box.min.abs().max(original_box.max.abs()).length();
+        let padding = radius * (1.0 - (max_angle / 2.0).cos());
+        bbox.map(|bbox| BoundingBox::new(bbox.min - padding, bbox.max + padding))
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::AnimatedTransform {
+            object: Box::new(self.object.describe(materials)?),
+            keyframes: self.keyframes.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittables::{MovingSphere, QuadBox, Sphere},
+        material::Material,
+    };
+
+    #[test]
+    fn follows_keyframes() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+
+        // a translation matches a moving sphere
+        let sphere: Arc<dyn Hittable> = Sphere::new(Vec3A::ZERO, 1.0, &mat).wrap();
+        let moving_sphere = MovingSphere::new(Vec3A::ZERO, Vec3A::X * 2.0, 0.0, 1.0, 1.0, &mat);
+        let animated = AnimatedTransform::new(
+            &sphere,
+            vec![
+                Keyframe::new(0.0),
+                Keyframe::new(1.0).with_translation(Vec3::X * 2.0),
+            ],
+        );
+        for time in [0.2, 0.5, 0.8] {
+            let ray = Ray::new(Vec3A::new(1.0, 5.0, 0.2), -Vec3A::Y, time);
+            let expected = moving_sphere.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            let rec = animated.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            assert!((rec.t - expected.t).abs() < 1e-4);
+            assert!(rec.normal.abs_diff_eq(expected.normal, 1e-4));
+        }
+
+        // a spinning box stays within its bounding box the whole time
+        let quadbox: Arc<dyn Hittable> =
+            QuadBox::new(Vec3A::new(1.0, 0.0, -0.5), Vec3A::new(3.0, 1.0, 0.5), &mat).wrap();
+        let spinning = AnimatedTransform::new(
+            &quadbox,
+            vec![
+                Keyframe::new(0.0),
+                Keyframe::new(0.5).with_rotation(Vec3::Y, 120.0),
+                Keyframe::new(1.0).with_rotation(Vec3::Y, 240.0),
+            ],
+        );
+        let bbox = spinning.bounding_box(0.0, 1.0).unwrap();
+        for step in 0..=100 {
+            let (scale, rotation, translation) = spinning.interpolate(step as f32 / 100.0);
+            for corner in quadbox.bounding_box(0.0, 1.0).unwrap().corners() {
+                let corner = rotation * (corner * scale) + translation;
+                assert!(corner.cmpge(bbox.min).all() && corner.cmple(bbox.max).all());
+            }
+        }
+    }
+}
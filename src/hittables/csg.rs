@@ -0,0 +1,216 @@
+//! Constructive solid geometry, combining two closed objects into one
+
+use std::{iter::Peekable, sync::Arc, vec::IntoIter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bounds::BoundingBox,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+};
+
+use super::{HitRecord, Hittable};
+
+/// How the two operands of a [Csg] are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CsgOperation {
+    /// Everything inside either operand
+    Union,
+    /// Everything inside both operands
+    Intersection,
+    /// Everything inside the left operand, but not the right one
+    Difference,
+}
+
+impl CsgOperation {
+    /// Returns whether a point is inside the combined object, given whether it is inside each operand
+    fn contains(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Two closed objects combined by a [CsgOperation]
+///
+/// Surfaces keep the material of the operand they come from,
+/// so the right operand of a difference lines the hole it carves.
+pub struct Csg {
+    operation: CsgOperation,
+    left: Arc<dyn Hittable>,
+    right: Arc<dyn Hittable>,
+}
+
+impl Csg {
+    /// Creates a new Csg combining `left` and `right`
+    pub fn new(
+        operation: CsgOperation,
+        left: &Arc<dyn Hittable>,
+        right: &Arc<dyn Hittable>,
+    ) -> Self {
+        Self {
+            operation,
+            left: Arc::clone(left),
+            right: Arc::clone(right),
+        }
+    }
+}
+
+/// Intersections of a ray with one operand, along with whether the ray is inside it
+struct Crossings {
+    hits: Peekable<IntoIter<HitRecord>>,
+    inside: bool,
+}
+
+impl Crossings {
+    /// Gathers the intersections past `t_min`, without an upper limit
+    ///
+    /// A limit could cut off the exit of a ray starting inside the object,
+    /// and with it the only sign of starting inside.
+    fn new(object: &dyn Hittable, ray: &Ray, t_min: f32) -> Self {
+        let hits = object.hit_all(ray, t_min, f32::INFINITY);
+        // a ray starting inside the object first hits it from the inside
+        let inside = hits.first().is_some_and(|rec| !rec.front_face);
+        Self {
+            hits: hits.into_iter().peekable(),
+            inside,
+        }
+    }
+
+    fn next_t(&mut self) -> Option<f32> {
+        self.hits.peek().map(|rec| rec.t)
+    }
+
+    /// Moves past the next intersection, returning it
+    fn advance(&mut self) -> Option<HitRecord> {
+        let rec = self.hits.next()?;
+        self.inside = rec.front_face;
+        Some(rec)
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let mut left = Crossings::new(self.left.as_ref(), ray, t_min);
+        let mut right = Crossings::new(self.right.as_ref(), ray, t_min);
+        let inside = self.operation.contains(left.inside, right.inside);
+
+        // walk through the crossings of both operands in order,
+        // until the ray crosses the boundary of the combined object
+        loop {
+            let from_left = match (left.next_t(), right.next_t()) {
+                (Some(l), Some(r)) => l <= r,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return None,
+            };
+            let mut rec = if from_left {
+                left.advance()?
+            } else {
+                right.advance()?
+            };
+
+            if self.operation.contains(left.inside, right.inside) != inside {
+                if rec.t > t_max {
+                    return None;
+                }
+                let mut outward_n = if rec.front_face {
+                    rec.normal
+                } else {
+                    -rec.normal
+                };
+                // the surface of a carved hole faces into the right operand
+                if !from_left && self.operation == CsgOperation::Difference {
+                    outward_n = -outward_n;
+                }
+                rec.set_face_normal(ray, outward_n);
+                return Some(rec);
+            }
+        }
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<BoundingBox> {
+        let left = self.left.bounding_box(time0, time1);
+        let right = self.right.bounding_box(time0, time1);
+        match self.operation {
+            CsgOperation::Union => Some(left?.union(&right?)),
+            CsgOperation::Intersection => match (left, right) {
+                (Some(left), Some(right)) => Some(left.intersection(&right)),
+                (left, right) => left.or(right),
+            },
+            CsgOperation::Difference => left,
+        }
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Csg {
+            operation: self.operation,
+            left: Box::new(self.left.describe(materials)?),
+            right: Box::new(self.right.describe(materials)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3A;
+
+    use super::*;
+    use crate::{
+        hittables::{QuadBox, Sphere},
+        material::Material,
+    };
+
+    #[test]
+    fn combines_spheres_and_boxes() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let sphere: Arc<dyn Hittable> = Sphere::new(Vec3A::ZERO, 1.0, &mat).wrap();
+        let half: Arc<dyn Hittable> =
+            QuadBox::new(Vec3A::new(0.0, -2.0, -2.0), Vec3A::splat(2.0), &mat).wrap();
+        let ray = Ray::new(Vec3A::new(5.0, 0.0, 0.0), -Vec3A::X, 0.0);
+
+        // the box is hit before the sphere
+        let union = Csg::new(CsgOperation::Union, &sphere, &half);
+        let rec = union.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-4);
+
+        // the half of the sphere inside the box
+        let intersection = Csg::new(CsgOperation::Intersection, &sphere, &half);
+        let rec = intersection.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-4);
+
+        // the other half, hit on the flat face carved by the box
+        let difference = Csg::new(CsgOperation::Difference, &sphere, &half);
+        let rec = difference.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!(rec.normal.abs_diff_eq(Vec3A::X, 1e-4));
+
+        // leaving the carved half from inside it
+        let ray = Ray::new(Vec3A::new(-0.5, 0.0, 0.0), Vec3A::X, 0.0);
+        let rec = difference.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 0.5).abs() < 1e-4);
+        assert!(!rec.front_face);
+        assert!(rec.normal.abs_diff_eq(-Vec3A::X, 1e-4));
+    }
+
+    #[test]
+    fn keeps_inside_state_beyond_t_max() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let left: Arc<dyn Hittable> = Sphere::new(Vec3A::ZERO, 1.0, &mat).wrap();
+        let right: Arc<dyn Hittable> = Sphere::new(Vec3A::X * 1.5, 1.0, &mat).wrap();
+        let union = Csg::new(CsgOperation::Union, &left, &right);
+
+        // starting inside the left sphere, entering the right one before leaving the left one
+        let ray = Ray::new(Vec3A::ZERO, Vec3A::X, 0.0);
+        let rec = union.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 2.5).abs() < 1e-4);
+        assert!(!rec.front_face);
+
+        // a t_max cutting off the exit of the left sphere must not make entering the right one a hit
+        assert!(union.hit(&ray, 0.001, 0.75).is_none());
+    }
+}
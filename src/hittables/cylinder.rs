@@ -0,0 +1,475 @@
+//! Implementations of Cylinder-like objects
+
+use std::{
+    f32::consts::{PI, TAU},
+    sync::Arc,
+};
+
+use glam::Vec3A;
+
+use crate::{
+    bounds::BoundingBox,
+    material::Material,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+    utils::geometry::{circle_extent, tangent_axes},
+};
+
+use super::{HitRecord, Hittable};
+
+/// Local frame along the axis of a cylinder-like object, from `p0` to `p1`
+#[derive(Debug)]
+struct AxisFrame {
+    p0: Vec3A,
+    p1: Vec3A,
+    /// unit vector from `p0` towards `p1`
+    axis: Vec3A,
+    height: f32,
+    u_axis: Vec3A,
+    v_axis: Vec3A,
+}
+
+/// A ray, split into components along and perpendicular to an [AxisFrame]'s axis
+struct LocalRay {
+    /// height of the origin along the axis
+    origin_y: f32,
+    /// change in height per unit of t
+    dir_y: f32,
+    /// offset of the origin from the axis
+    origin_r: Vec3A,
+    /// change in the offset from the axis per unit of t
+    dir_r: Vec3A,
+}
+
+impl AxisFrame {
+    fn new(p0: Vec3A, p1: Vec3A) -> Self {
+        let axis = (p1 - p0).normalize();
+        let (u_axis, v_axis) = tangent_axes(axis);
+        Self {
+            p0,
+            p1,
+            axis,
+            height: p0.distance(p1),
+            u_axis,
+            v_axis,
+        }
+    }
+
+    fn local_ray(&self, ray: &Ray) -> LocalRay {
+        let oc = ray.origin - self.p0;
+        let origin_y = oc.dot(self.axis);
+        let dir_y = ray.direction.dot(self.axis);
+        LocalRay {
+            origin_y,
+            dir_y,
+            origin_r: oc - origin_y * self.axis,
+            dir_r: ray.direction - dir_y * self.axis,
+        }
+    }
+
+    /// Returns the u coordinate for an offset from the axis, going around the axis once
+    fn angle_u(&self, radial: Vec3A) -> f32 {
+        let phi = radial.dot(self.v_axis).atan2(radial.dot(self.u_axis));
+        (phi + PI) / TAU
+    }
+
+    /// Returns the u/v coordinates for an offset on a cap, mapping the cap's enclosing square
+    fn cap_uv(&self, radial: Vec3A, radius: f32) -> (f32, f32) {
+        (
+            0.5 + 0.5 * radial.dot(self.u_axis) / radius,
+            0.5 + 0.5 * radial.dot(self.v_axis) / radius,
+        )
+    }
+}
+
+/// A candidate intersection, before the closest one is picked
+struct Candidate {
+    t: f32,
+    outward_n: Vec3A,
+    u: f32,
+    v: f32,
+}
+
+/// Returns the record of the closest candidate within the given t range
+fn closest_hit(
+    candidates: impl IntoIterator<Item = Candidate>,
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+    material: &Arc<Material>,
+) -> Option<HitRecord> {
+    let closest = candidates
+        .into_iter()
+        .filter(|c| (t_min..=t_max).contains(&c.t))
+        .min_by(|a, b| a.t.total_cmp(&b.t))?;
+
+    let mut rec = HitRecord {
+        point: ray.at(closest.t),
+        normal: closest.outward_n,
+        material: Arc::clone(material),
+        t: closest.t,
+        u: closest.u,
+        v: closest.v,
+        front_face: true,
+    };
+    rec.set_face_normal(ray, closest.outward_n);
+    Some(rec)
+}
+
+/// Returns the roots of `a * t^2 + 2 * half_b * t + c`, if any
+fn solve_quadratic(a: f32, half_b: f32, c: f32) -> Option<[f32; 2]> {
+    if a.abs() < 1e-8 {
+        // linear, with only one root
+        let t = -c / (2.0 * half_b);
+        return t.is_finite().then_some([t, t]);
+    }
+
+    let discrim = half_b * half_b - a * c;
+    if discrim < 0.0 {
+        return None;
+    }
+    let sqrt_d = discrim.sqrt();
+    Some([(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a])
+}
+
+/// A cylinder between two points, optionally closed off by caps at each end
+#[derive(Debug)]
+pub struct Cylinder {
+    frame: AxisFrame,
+    radius: f32,
+    capped: bool,
+    pub material: Arc<Material>,
+}
+
+impl Cylinder {
+    /// Creates a new, capped, Cylinder with its base at `p0` and its top at `p1`.
+    pub fn new(p0: Vec3A, p1: Vec3A, radius: f32, m: &Arc<Material>) -> Self {
+        Self {
+            frame: AxisFrame::new(p0, p1),
+            radius,
+            capped: true,
+            material: Arc::clone(m),
+        }
+    }
+
+    /// Sets whether the cylinder is closed off by caps, or open like a tube
+    pub fn with_caps(mut self, capped: bool) -> Self {
+        self.capped = capped;
+        self
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let frame = &self.frame;
+        let local = frame.local_ray(ray);
+        let mut candidates = Vec::with_capacity(4);
+
+        // body, where the distance to the axis equals the radius
+        let a = local.dir_r.length_squared();
+        let half_b = local.origin_r.dot(local.dir_r);
+        let c = local.origin_r.length_squared() - self.radius * self.radius;
+        if let Some(roots) = solve_quadratic(a, half_b, c) {
+            for t in roots {
+                let y = local.origin_y + t * local.dir_y;
+                if (0.0..=frame.height).contains(&y) {
+                    let radial = local.origin_r + t * local.dir_r;
+                    candidates.push(Candidate {
+                        t,
+                        outward_n: radial / self.radius,
+                        u: frame.angle_u(radial),
+                        v: y / frame.height,
+                    });
+                }
+            }
+        }
+
+        if self.capped {
+            for (y, outward_n) in [(0.0, -frame.axis), (frame.height, frame.axis)] {
+                let t = (y - local.origin_y) / local.dir_y;
+                let radial = local.origin_r + t * local.dir_r;
+                if radial.length_squared() <= self.radius * self.radius {
+                    let (u, v) = frame.cap_uv(radial, self.radius);
+                    candidates.push(Candidate { t, outward_n, u, v });
+                }
+            }
+        }
+
+        closest_hit(candidates, ray, t_min, t_max, &self.material)
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        let extent = self.radius * circle_extent(self.frame.axis);
+        Some(BoundingBox::new(
+            self.frame.p0.min(self.frame.p1) - extent,
+            self.frame.p0.max(self.frame.p1) + extent,
+        ))
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Cylinder {
+            p0: self.frame.p0,
+            p1: self.frame.p1,
+            radius: self.radius,
+            capped: self.capped,
+            material: materials.name(&self.material)?,
+        })
+    }
+}
+
+/// A cone between two points, with differing radii at each end.
+///
+/// A radius of zero ends the cone in a point, otherwise the cone is truncated,
+/// and optionally closed off by a cap.
+#[derive(Debug)]
+pub struct Cone {
+    frame: AxisFrame,
+    radius0: f32,
+    radius1: f32,
+    capped: bool,
+    pub material: Arc<Material>,
+}
+
+impl Cone {
+    /// Creates a new, capped, Cone with a radius of `radius0` at `p0`, and `radius1` at `p1`.
+    pub fn new(p0: Vec3A, p1: Vec3A, radius0: f32, radius1: f32, m: &Arc<Material>) -> Self {
+        Self {
+            frame: AxisFrame::new(p0, p1),
+            radius0,
+            radius1,
+            capped: true,
+            material: Arc::clone(m),
+        }
+    }
+
+    /// Sets whether the cone's ends are closed off by caps
+    pub fn with_caps(mut self, capped: bool) -> Self {
+        self.capped = capped;
+        self
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let frame = &self.frame;
+        let local = frame.local_ray(ray);
+        let mut candidates = Vec::with_capacity(4);
+
+        // body, where the distance to the axis equals the radius at that height,
+        // with the radius changing linearly along the ray: radius_a + radius_b * t
+        let slope = (self.radius1 - self.radius0) / frame.height;
+        let radius_a = self.radius0 + slope * local.origin_y;
+        let radius_b = slope * local.dir_y;
+
+        let a = local.dir_r.length_squared() - radius_b * radius_b;
+        let half_b = local.origin_r.dot(local.dir_r) - radius_a * radius_b;
+        let c = local.origin_r.length_squared() - radius_a * radius_a;
+        if let Some(roots) = solve_quadratic(a, half_b, c) {
+            for t in roots {
+                let y = local.origin_y + t * local.dir_y;
+                if (0.0..=frame.height).contains(&y) {
+                    let radial = local.origin_r + t * local.dir_r;
+                    // tilt the normal away from the axis by the slope of the side
+                    let outward_n = (radial.normalize_or_zero() * frame.height
+                        + frame.axis * (self.radius0 - self.radius1))
+                        .normalize();
+                    candidates.push(Candidate {
+                        t,
+                        outward_n,
+                        u: frame.angle_u(radial),
+                        v: y / frame.height,
+                    });
+                }
+            }
+        }
+
+        if self.capped {
+            let caps = [
+                (0.0, self.radius0, -frame.axis),
+                (frame.height, self.radius1, frame.axis),
+            ];
+            for (y, radius, outward_n) in caps {
+                if radius <= 0.0 {
+                    continue;
+                }
+                let t = (y - local.origin_y) / local.dir_y;
+                let radial = local.origin_r + t * local.dir_r;
+                if radial.length_squared() <= radius * radius {
+                    let (u, v) = frame.cap_uv(radial, radius);
+                    candidates.push(Candidate { t, outward_n, u, v });
+                }
+            }
+        }
+
+        closest_hit(candidates, ray, t_min, t_max, &self.material)
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        let extent = circle_extent(self.frame.axis);
+        let (p0, p1) = (self.frame.p0, self.frame.p1);
+        Some(BoundingBox::new(
+            (p0 - self.radius0 * extent).min(p1 - self.radius1 * extent),
+            (p0 + self.radius0 * extent).max(p1 + self.radius1 * extent),
+        ))
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Cone {
+            p0: self.frame.p0,
+            p1: self.frame.p1,
+            radius0: self.radius0,
+            radius1: self.radius1,
+            capped: self.capped,
+            material: materials.name(&self.material)?,
+        })
+    }
+}
+
+/// A capsule, consisting of a cylinder between two points with a hemisphere at each end
+#[derive(Debug)]
+pub struct Capsule {
+    frame: AxisFrame,
+    radius: f32,
+    pub material: Arc<Material>,
+}
+
+impl Capsule {
+    /// Creates a new Capsule around the line from `p0` to `p1`.
+    pub fn new(p0: Vec3A, p1: Vec3A, radius: f32, m: &Arc<Material>) -> Self {
+        Self {
+            frame: AxisFrame::new(p0, p1),
+            radius,
+            material: Arc::clone(m),
+        }
+    }
+}
+
+impl Hittable for Capsule {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let frame = &self.frame;
+        let local = frame.local_ray(ray);
+        let mut candidates = Vec::with_capacity(6);
+
+        // v runs from the bottom of the lower hemisphere to the top of the upper one
+        let total_height = frame.height + 2.0 * self.radius;
+        let mut push_candidate = |t: f32, y: f32, outward_n: Vec3A| {
+            candidates.push(Candidate {
+                t,
+                outward_n,
+                u: frame.angle_u(outward_n),
+                v: (y + self.radius) / total_height,
+            })
+        };
+
+        // body, like an open cylinder
+        let a = local.dir_r.length_squared();
+        let half_b = local.origin_r.dot(local.dir_r);
+        let c = local.origin_r.length_squared() - self.radius * self.radius;
+        if let Some(roots) = solve_quadratic(a, half_b, c) {
+            for t in roots {
+                let y = local.origin_y + t * local.dir_y;
+                if (0.0..=frame.height).contains(&y) {
+                    let radial = local.origin_r + t * local.dir_r;
+                    push_candidate(t, y, radial / self.radius);
+                }
+            }
+        }
+
+        // hemispheres, each only counting on its side beyond the body
+        let oc = ray.origin - frame.p0;
+        for (center_y, beyond) in [(0.0, -1.0), (frame.height, 1.0)] {
+            let to_origin = oc - center_y * frame.axis;
+            let a = ray.direction.length_squared();
+            let half_b = to_origin.dot(ray.direction);
+            let c = to_origin.length_squared() - self.radius * self.radius;
+            if let Some(roots) = solve_quadratic(a, half_b, c) {
+                for t in roots {
+                    let y = local.origin_y + t * local.dir_y;
+                    if (y - center_y) * beyond >= 0.0 {
+                        let outward_n = (to_origin + t * ray.direction) / self.radius;
+                        push_candidate(t, y, outward_n);
+                    }
+                }
+            }
+        }
+
+        closest_hit(candidates, ray, t_min, t_max, &self.material)
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        Some(BoundingBox::new(
+            self.frame.p0.min(self.frame.p1) - self.radius,
+            self.frame.p0.max(self.frame.p1) + self.radius,
+        ))
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Capsule {
+            p0: self.frame.p0,
+            p1: self.frame.p1,
+            radius: self.radius,
+            material: materials.name(&self.material)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_body_and_caps() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let cylinder = Cylinder::new(Vec3A::ZERO, Vec3A::Y * 2.0, 1.0, &mat);
+        let capsule = Capsule::new(Vec3A::ZERO, Vec3A::Y * 2.0, 1.0, &mat);
+
+        // from the side, hitting the body
+        let ray = Ray::new(Vec3A::new(5.0, 1.0, 0.0), -Vec3A::X, 0.0);
+        let rec = cylinder.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!(rec.normal.abs_diff_eq(Vec3A::X, 1e-4));
+
+        // from above, hitting the top cap and the top of the hemisphere
+        let ray = Ray::new(Vec3A::new(0.0, 5.0, 0.0), -Vec3A::Y, 0.0);
+        let rec = cylinder.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-4);
+        assert!(rec.normal.abs_diff_eq(Vec3A::Y, 1e-4));
+        let rec = capsule.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-4);
+
+        // an open cylinder is hit on its inside instead
+        let open = Cylinder::new(Vec3A::ZERO, Vec3A::Y * 2.0, 1.0, &mat).with_caps(false);
+        let ray = Ray::new(Vec3A::new(0.0, 5.0, 0.0), Vec3A::new(0.3, -1.0, 0.0), 0.0);
+        let rec = open.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!(!rec.front_face);
+
+        // a truncated cone narrowing from a radius of 2 to 1, its side sloping outwards
+        let cone = Cone::new(Vec3A::ZERO, Vec3A::Y * 2.0, 2.0, 1.0, &mat);
+        let ray = Ray::new(Vec3A::new(5.0, 1.0, 0.0), -Vec3A::X, 0.0);
+        let rec = cone.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 3.5).abs() < 1e-4);
+        assert!(rec.front_face);
+        let side_n = Vec3A::new(2.0, 1.0, 0.0).normalize();
+        assert!(rec.normal.abs_diff_eq(side_n, 1e-4));
+
+        // its caps, from above and below
+        let ray = Ray::new(Vec3A::new(0.5, 5.0, 0.0), -Vec3A::Y, 0.0);
+        let rec = cone.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-4);
+        assert!(rec.normal.abs_diff_eq(Vec3A::Y, 1e-4));
+        let ray = Ray::new(Vec3A::new(1.5, -5.0, 0.0), Vec3A::Y, 0.0);
+        let rec = cone.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-4);
+        assert!(rec.normal.abs_diff_eq(-Vec3A::Y, 1e-4));
+
+        // a radius of zero ends in a point, without a cap to hit from above
+        let pointed = Cone::new(Vec3A::ZERO, Vec3A::Y * 2.0, 1.0, 0.0, &mat);
+        let ray = Ray::new(Vec3A::new(0.5, 5.0, 0.0), -Vec3A::Y, 0.0);
+        let rec = pointed.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-4);
+        assert!(rec.normal.abs_diff_eq(side_n, 1e-4));
+        let ray = Ray::new(Vec3A::new(1.5, 5.0, 0.0), -Vec3A::Y, 0.0);
+        assert!(pointed.hit(&ray, 0.001, f32::INFINITY).is_none());
+    }
+}
@@ -0,0 +1,388 @@
+//! Terrain defined by a grid of heights, from a grayscale image or a noise function
+
+use std::{fmt::Display, path::PathBuf, sync::Arc};
+
+use glam::{UVec2, Vec3A};
+use noise::NoiseFn;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bounds::BoundingBox,
+    material::Material,
+    ray::Ray,
+    scenes::{is_default_perlin, MaterialNames, ObjectDescription},
+};
+
+use super::{intersect_triangle, HitRecord, Hittable};
+
+/// Where the heights of a [Heightfield] come from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum HeightfieldSource {
+    /// A grayscale image, with one sample per pixel and white being the highest
+    Image { path: PathBuf },
+    /// Perlin noise sampled at `scale` times the x and z coordinates, on a grid of `resolution` samples
+    Perlin { resolution: UVec2, scale: f32 },
+}
+
+/// Errors possible when creating a [Heightfield]
+#[derive(Debug)]
+pub enum HeightfieldError {
+    /// The image could not be loaded
+    Load(image::ImageError),
+    /// Fewer than 2 samples along either side, which don't make up any cells
+    TooSmall(UVec2),
+}
+
+impl Display for HeightfieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeightfieldError::Load(e) => write!(f, "{}", e),
+            HeightfieldError::TooSmall(resolution) => write!(
+                f,
+                "heightfield needs at least 2x2 samples, got {}x{}",
+                resolution.x, resolution.y
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HeightfieldError {}
+
+/// A terrain surface over a rectangular footprint in the XZ plane
+///
+/// Heights are sampled on a regular grid, each cell of which is split into two triangles.
+/// Rays walk the grid cell by cell, skipping cells that they pass above or below,
+/// so large grids are cheap to intersect.
+/// Texture coordinates span the footprint, lining up with the image the heights came from.
+pub struct Heightfield {
+    /// Where the heights came from, if it can be described
+    source: Option<HeightfieldSource>,
+    /// Number of samples along x and z
+    resolution: UVec2,
+    /// Heights between 0 and 1, row by row along z
+    heights: Vec<f32>,
+    /// Shading normals at each sample
+    normals: Vec<Vec3A>,
+    /// Lowest and highest world y coordinate within each cell
+    cell_bounds: Vec<(f32, f32)>,
+    /// Corner of the footprint, at height 0
+    min: Vec3A,
+    /// Size of the footprint, and the height of a sample of 1
+    size: Vec3A,
+    pub material: Arc<Material>,
+}
+
+impl Heightfield {
+    /// Creates a new Heightfield from the grayscale image at `file_path`
+    ///
+    /// The image is stretched across a footprint of `size.x` by `size.z` units starting at `min`,
+    /// with the top row of pixels at the lowest z coordinate, and white pixels `size.y` units high.
+    pub fn from_image(
+        file_path: PathBuf,
+        min: Vec3A,
+        size: Vec3A,
+        m: &Arc<Material>,
+    ) -> Result<Self, HeightfieldError> {
+        let img = image::open(&file_path)
+            .map_err(HeightfieldError::Load)?
+            .to_luma32f();
+        let resolution = UVec2::new(img.width(), img.height());
+        let heights = img.into_raw();
+
+        let source = HeightfieldSource::Image { path: file_path };
+        Self::from_heights(Some(source), resolution, heights, min, size, m)
+    }
+
+    /// Creates a new Heightfield by sampling `noise` on a grid of `resolution` samples
+    ///
+    /// Samples are taken at `scale` times the x and z coordinates of the footprint.
+    /// Noise values from -1 to 1 give heights from `min.y` to `min.y + size.y`.
+    pub fn from_noise<N>(
+        noise: &N,
+        scale: f32,
+        resolution: UVec2,
+        min: Vec3A,
+        size: Vec3A,
+        m: &Arc<Material>,
+    ) -> Result<Self, HeightfieldError>
+    where
+        N: NoiseFn<f64, 2> + 'static,
+    {
+        let cells = (resolution.max(UVec2::splat(2)) - 1).as_vec2();
+        let cell_size = Vec3A::new(size.x / cells.x, 0.0, size.z / cells.y);
+        let heights = (0..resolution.y)
+            .flat_map(|z| (0..resolution.x).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let point = scale * (min + Vec3A::new(x as f32, 0.0, z as f32) * cell_size);
+                let noise_val = noise.get([point.x as f64, point.z as f64]);
+                (0.5 * (noise_val + 1.0)).clamp(0.0, 1.0) as f32
+            })
+            .collect();
+
+        let source =
+            is_default_perlin(noise).then_some(HeightfieldSource::Perlin { resolution, scale });
+        Self::from_heights(source, resolution, heights, min, size, m)
+    }
+
+    fn from_heights(
+        source: Option<HeightfieldSource>,
+        resolution: UVec2,
+        heights: Vec<f32>,
+        min: Vec3A,
+        size: Vec3A,
+        m: &Arc<Material>,
+    ) -> Result<Self, HeightfieldError> {
+        if resolution.x < 2 || resolution.y < 2 {
+            return Err(HeightfieldError::TooSmall(resolution));
+        }
+
+        let mut field = Self {
+            source,
+            resolution,
+            heights,
+            normals: vec![],
+            cell_bounds: vec![],
+            min,
+            size,
+            material: Arc::clone(m),
+        };
+
+        // shading normals from the slope between neighbouring samples
+        let (nx, nz) = (resolution.x as usize, resolution.y as usize);
+        field.normals = (0..nz)
+            .flat_map(|z| (0..nx).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let (x0, x1) = (x.saturating_sub(1), (x + 1).min(nx - 1));
+                let (z0, z1) = (z.saturating_sub(1), (z + 1).min(nz - 1));
+                let dx = field.vertex(x1, z) - field.vertex(x0, z);
+                let dz = field.vertex(x, z1) - field.vertex(x, z0);
+                dz.cross(dx).normalize()
+            })
+            .collect();
+
+        field.cell_bounds = (0..nz - 1)
+            .flat_map(|z| (0..nx - 1).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                let ys = field.cell_corners(x, z).map(|corner| corner.y);
+                let low = ys.into_iter().fold(f32::INFINITY, f32::min);
+                let high = ys.into_iter().fold(f32::NEG_INFINITY, f32::max);
+                (low, high)
+            })
+            .collect();
+
+        Ok(field)
+    }
+
+    /// Returns the size of a single cell along x and z, and the height of a sample of 1 as y
+    fn cell_size(&self) -> Vec3A {
+        let cells = (self.resolution - 1).as_vec2();
+        Vec3A::new(self.size.x / cells.x, self.size.y, self.size.z / cells.y)
+    }
+
+    /// Returns the index of the sample at the given grid coordinates
+    fn index(&self, x: usize, z: usize) -> usize {
+        z * self.resolution.x as usize + x
+    }
+
+    /// Returns the index of the cell at the given grid coordinates
+    fn cell_index(&self, x: usize, z: usize) -> usize {
+        z * (self.resolution.x as usize - 1) + x
+    }
+
+    /// Returns the position of the sample at the given grid coordinates
+    fn vertex(&self, x: usize, z: usize) -> Vec3A {
+        let height = self.heights[self.index(x, z)];
+        self.min + Vec3A::new(x as f32, height, z as f32) * self.cell_size()
+    }
+
+    /// Returns the four corners of a cell, going around it
+    fn cell_corners(&self, x: usize, z: usize) -> [Vec3A; 4] {
+        [
+            self.vertex(x, z),
+            self.vertex(x + 1, z),
+            self.vertex(x + 1, z + 1),
+            self.vertex(x, z + 1),
+        ]
+    }
+
+    /// Intersects the ray with the two triangles making up a cell
+    fn hit_cell(&self, ray: &Ray, x: usize, z: usize, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let corners = self.cell_corners(x, z);
+        let indices = [
+            self.index(x, z),
+            self.index(x + 1, z),
+            self.index(x + 1, z + 1),
+            self.index(x, z + 1),
+        ];
+
+        // both triangles are wound so that their normals face up
+        let mut closest = None;
+        let mut t_closest = t_max;
+        for triangle in [[0, 2, 1], [0, 3, 2]] {
+            let points = triangle.map(|i| corners[i]);
+            if let Some((t, b1, b2)) = intersect_triangle(ray, points, t_min, t_closest) {
+                t_closest = t;
+                closest = Some((triangle, t, b1, b2));
+            }
+        }
+        let (triangle, t, b1, b2) = closest?;
+        let [p0, p1, p2] = triangle.map(|i| corners[i]);
+        let [n0, n1, n2] = triangle.map(|i| self.normals[indices[i]]);
+
+        // the geometric normal decides which side was hit, like a Triangle
+        let b0 = 1.0 - b1 - b2;
+        let outward_n = (p1 - p0).cross(p2 - p0).normalize();
+        let shading_n = (b0 * n0 + b1 * n1 + b2 * n2).normalize();
+        let front_face = ray.direction.dot(outward_n) < 0.0;
+
+        let point = ray.at(t);
+        let offset = (point - self.min) / self.size;
+        Some(HitRecord {
+            point,
+            normal: if front_face { shading_n } else { -shading_n },
+            material: Arc::clone(&self.material),
+            t,
+            u: offset.x,
+            v: 1.0 - offset.z,
+            front_face,
+        })
+    }
+}
+
+/// Returns the direction to step through the grid along an axis,
+/// the distance along the ray to cross one cell, and the distance to the first cell boundary
+fn grid_step(position: f32, cell: i64, direction: f32, t: f32) -> (i64, f32, f32) {
+    if direction > 0.0 {
+        (
+            1,
+            direction.recip(),
+            t + (cell as f32 + 1.0 - position) / direction,
+        )
+    } else if direction < 0.0 {
+        (
+            -1,
+            -direction.recip(),
+            t + (cell as f32 - position) / direction,
+        )
+    } else {
+        (0, f32::INFINITY, f32::INFINITY)
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let bbox = self.bounding_box(0.0, 0.0)?;
+        let (t_enter, t_exit) = bbox.clip(ray, t_min, t_max)?;
+
+        // walk the cells under the ray in grid coordinates, where cells are 1 unit wide
+        let cell_size = self.cell_size();
+        let cells = (self.resolution - 1).as_ivec2();
+        let start = (ray.at(t_enter) - self.min) / cell_size;
+        let direction = ray.direction / cell_size;
+
+        let mut x = (start.x.floor() as i64).clamp(0, cells.x as i64 - 1);
+        let mut z = (start.z.floor() as i64).clamp(0, cells.y as i64 - 1);
+        let (step_x, delta_x, mut next_x) = grid_step(start.x, x, direction.x, t_enter);
+        let (step_z, delta_z, mut next_z) = grid_step(start.z, z, direction.z, t_enter);
+
+        let mut t_cell = t_enter;
+        loop {
+            let t_leave = next_x.min(next_z).min(t_exit);
+
+            // skip cells that the ray passes entirely above or below
+            let y0 = ray.origin.y + t_cell * ray.direction.y;
+            let y1 = ray.origin.y + t_leave * ray.direction.y;
+            let (low, high) = self.cell_bounds[self.cell_index(x as usize, z as usize)];
+            if y0.min(y1) <= high && y0.max(y1) >= low {
+                let rec = self.hit_cell(ray, x as usize, z as usize, t_min, t_max);
+                if rec.is_some() {
+                    return rec;
+                }
+            }
+
+            if t_leave >= t_exit {
+                return None;
+            }
+            if next_x < next_z {
+                x += step_x;
+                t_cell = next_x;
+                next_x += delta_x;
+            } else {
+                z += step_z;
+                t_cell = next_z;
+                next_z += delta_z;
+            }
+            if !(0..cells.x as i64).contains(&x) || !(0..cells.y as i64).contains(&z) {
+                return None;
+            }
+        }
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        // padded, in case the heightfield is flat
+        Some(BoundingBox::new(
+            self.min - Vec3A::new(0.0, 0.0001, 0.0),
+            self.min + self.size + Vec3A::new(0.0, 0.0001, 0.0),
+        ))
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        let source = match self.source.clone()? {
+            HeightfieldSource::Image { path } => HeightfieldSource::Image {
+                path: materials.path(&path),
+            },
+            source => source,
+        };
+        Some(ObjectDescription::Heightfield {
+            source,
+            min: self.min,
+            size: self.size,
+            material: materials.name(&self.material)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittables::{HittableList, Triangle};
+
+    #[test]
+    fn matches_triangles() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let field = Heightfield::from_noise(
+            &noise::Perlin::default(),
+            0.7,
+            UVec2::new(9, 7),
+            Vec3A::new(-2.0, -1.0, -1.5),
+            Vec3A::new(4.0, 2.0, 3.0),
+            &mat,
+        )
+        .unwrap();
+
+        // the same surface, built from individual triangles
+        let mut triangles: HittableList = vec![];
+        for z in 0..6 {
+            for x in 0..8 {
+                let [c0, c1, c2, c3] = field.cell_corners(x, z);
+                triangles.push(Triangle::new(c0, c2, c1, &mat).wrap());
+                triangles.push(Triangle::new(c0, c3, c2, &mat).wrap());
+            }
+        }
+
+        for i in 0..200 {
+            let angle = i as f32 * 0.37;
+            let origin = Vec3A::new(3.0 * angle.cos(), 2.0 + (i % 5) as f32, 3.0 * angle.sin());
+            let target = Vec3A::new((i % 7) as f32 - 3.0, -1.0, (i % 3) as f32 - 1.0);
+            let ray = Ray::new(origin, target - origin, 0.0);
+
+            let expected = triangles.hit(&ray, 0.001, f32::INFINITY).map(|rec| rec.t);
+            let actual = field.hit(&ray, 0.001, f32::INFINITY).map(|rec| rec.t);
+            match (expected, actual) {
+                (Some(expected), Some(actual)) => assert!((expected - actual).abs() < 1e-4),
+                (expected, actual) => assert_eq!(expected, actual, "ray {}", i),
+            }
+        }
+    }
+}
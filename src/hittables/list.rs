@@ -2,9 +2,16 @@
 
 use std::sync::Arc;
 
-use crate::{bounds::BoundingBox, ray::Ray};
+use glam::Vec3A;
+use rand::{Rng, RngCore};
 
-use super::{HitRecord, Hittable};
+use crate::{
+    bounds::BoundingBox,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+};
+
+use super::{gather_lights, HitRecord, Hittable};
 
 /// Type alias for a vector of objects implementing [Hittable]
 pub type HittableList = Vec<Arc<dyn Hittable>>;
@@ -30,11 +37,104 @@ impl Hittable for HittableList {
         }
 
         // applies the bounding_box fn to all hittables,
-        // filters out those returning `None`,
         // and reduces to a single bounding box through
         // repeated applications of the union fn.
+        // A single unbounded hittable makes the whole list unbounded.
         self.iter()
-            .filter_map(|hittable| hittable.bounding_box(time0, time1))
-            .reduce(|acc, bbox| acc.union(&bbox))
+            .map(|hittable| hittable.bounding_box(time0, time1))
+            .reduce(|acc, bbox| Some(acc?.union(&bbox?)))?
+    }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        gather_lights(self.iter(), lights);
+    }
+
+    /// Samples one of the objects, each as likely as the others
+    fn sample_direction(&self, origin: Vec3A, rng: &mut dyn RngCore) -> Option<Vec3A> {
+        if self.is_empty() {
+            return None;
+        }
+        let idx = rng.gen_range(0..self.len());
+        self[idx].sample_direction(origin, rng)
+    }
+
+    fn pdf_value(&self, origin: Vec3A, direction: Vec3A) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let sum: f32 = self
+            .iter()
+            .map(|hittable| hittable.pdf_value(origin, direction))
+            .sum();
+        sum / self.len() as f32
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        let objects = self
+            .iter()
+            .map(|hittable| hittable.describe(materials))
+            .collect::<Option<_>>()?;
+        Some(ObjectDescription::List { objects })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{
+        hittables::{Quad, Sphere},
+        material::Material,
+        textures::SolidColor,
+        utils::random::rand_vec3_in_unit_sphere,
+    };
+
+    #[test]
+    fn samples_lights() {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+        let light = Arc::new(Material::DiffuseLight {
+            albedo: Arc::new(SolidColor::new(Vec3A::ONE)),
+            brightness: 1.0,
+        });
+        let lights: HittableList = vec![
+            Quad::new(
+                Vec3A::new(-1.0, 2.0, -1.0),
+                Vec3A::new(-1.0, 2.0, 1.0),
+                Vec3A::new(1.0, 2.0, 1.0),
+                Vec3A::new(1.0, 2.0, -1.0),
+                &light,
+            )
+            .wrap(),
+            Sphere::new(Vec3A::new(0.0, -3.0, 1.0), 1.0, &light).wrap(),
+        ];
+        let origin = Vec3A::new(0.2, 0.0, 0.1);
+        const SAMPLES: usize = 200_000;
+
+        // each sample hits a light, and averaging 1 / pdf estimates the solid angle they cover
+        let mut inverse_pdfs = 0.0;
+        for _ in 0..SAMPLES {
+            let direction = lights.sample_direction(origin, &mut rng).unwrap();
+            let ray = Ray::new(origin, direction, 0.0);
+            assert!(lights.hit(&ray, 0.001, f32::INFINITY).is_some());
+            inverse_pdfs += lights.pdf_value(origin, direction).recip();
+        }
+        let sampled_angle = inverse_pdfs / SAMPLES as f32;
+
+        // compare against the fraction of uniformly random directions hitting them
+        let hits = (0..SAMPLES)
+            .filter(|_| {
+                let ray = Ray::new(origin, rand_vec3_in_unit_sphere(&mut rng), 0.0);
+                lights.hit(&ray, 0.001, f32::INFINITY).is_some()
+            })
+            .count();
+        let uniform_angle = 4.0 * std::f32::consts::PI * hits as f32 / SAMPLES as f32;
+
+        assert!(
+            (sampled_angle - uniform_angle).abs() < 0.03 * uniform_angle,
+            "{} != {}",
+            sampled_angle,
+            uniform_angle
+        );
     }
 }
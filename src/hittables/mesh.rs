@@ -0,0 +1,247 @@
+//! Triangle meshes loaded from Wavefront OBJ files
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use glam::{Vec2, Vec3A};
+
+use crate::{
+    bounds::BoundingBox,
+    bvh::LinearBvh,
+    color::Color,
+    material::Material,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+    textures::{ImageMap, Texture},
+};
+
+use super::{HitRecord, Hittable, HittableList, Triangle};
+
+/// A mesh of [Triangle]s, loaded from a [Wavefront OBJ](https://en.wikipedia.org/wiki/Wavefront_.obj_file) file
+///
+/// The triangles are placed into their own [LinearBvh].
+pub struct TriangleMesh {
+    /// Path to the OBJ file the mesh was loaded from
+    file_path: PathBuf,
+    /// Material used for faces without a material of their own
+    material: Arc<Material>,
+    /// Hierarchy of the mesh's triangles
+    triangles: LinearBvh,
+}
+
+/// Errors possible when loading a [TriangleMesh]
+#[derive(Debug)]
+pub enum MeshError {
+    /// The OBJ file could not be loaded
+    Load(tobj::LoadError),
+    /// The OBJ file does not contain any faces
+    Empty,
+    /// A texture of the MTL file could not be loaded
+    Texture(PathBuf, image::ImageError),
+}
+
+impl Display for MeshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshError::Load(e) => write!(f, "{}", e),
+            MeshError::Empty => write!(f, "mesh contains no faces"),
+            MeshError::Texture(path, e) => write!(f, "failed to load texture {:?}: {}", path, e),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+impl TriangleMesh {
+    /// Loads a new TriangleMesh from the OBJ file at `file_path`.
+    ///
+    /// Materials from the accompanying MTL file are mapped onto [Material]s,
+    /// faces without one use the given `material` instead.
+    pub fn from_obj(file_path: PathBuf, material: &Arc<Material>) -> Result<Self, MeshError> {
+        let (models, mtl_result) =
+            tobj::load_obj(&file_path, &tobj::GPU_LOAD_OPTIONS).map_err(MeshError::Load)?;
+
+        let obj_dir = file_path.parent().unwrap_or_else(|| Path::new(""));
+        let materials: Vec<Arc<Material>> = match mtl_result {
+            Ok(mtls) => mtls
+                .iter()
+                .map(|mtl| convert_material(mtl, obj_dir).map(Arc::new))
+                .collect::<Result<_, _>>()?,
+            Err(e) => {
+                eprintln!("Failed to load materials of {:?}: {}", file_path, e);
+                vec![]
+            }
+        };
+
+        let mut triangles: HittableList = vec![];
+        for model in models.iter() {
+            let mesh = &model.mesh;
+            let mat = mesh
+                .material_id
+                .and_then(|id| materials.get(id))
+                .unwrap_or(material);
+
+            let position = |idx: usize| Vec3A::from_slice(&mesh.positions[3 * idx..3 * idx + 3]);
+            let normal = |idx: usize| Vec3A::from_slice(&mesh.normals[3 * idx..3 * idx + 3]);
+            let uv = |idx: usize| Vec2::from_slice(&mesh.texcoords[2 * idx..2 * idx + 2]);
+
+            for face in mesh.indices.chunks_exact(3) {
+                let [i0, i1, i2] = [face[0] as usize, face[1] as usize, face[2] as usize];
+                let mut tri = Triangle::new(position(i0), position(i1), position(i2), mat);
+                if !mesh.normals.is_empty() {
+                    tri = tri.with_normals([normal(i0), normal(i1), normal(i2)]);
+                }
+                if !mesh.texcoords.is_empty() {
+                    tri = tri.with_uvs([uv(i0), uv(i1), uv(i2)]);
+                }
+                triangles.push(tri.wrap());
+            }
+        }
+
+        // triangles are always bounded, so an empty mesh is the only possible error
+        let triangles = LinearBvh::new(triangles, 0.0, 1.0).map_err(|_| MeshError::Empty)?;
+
+        Ok(Self {
+            file_path,
+            material: Arc::clone(material),
+            triangles,
+        })
+    }
+}
+
+/// Maps a material from a MTL file onto the closest [Material]
+///
+/// * emissive materials (`Ke`) become [Material::DiffuseLight]
+/// * transparent materials (`d` < 1 or a refracting `illum`) become [Material::Dielectric]
+/// * reflective materials (a reflecting `illum`) become [Material::Metal]
+/// * anything else becomes [Material::Lambertian]
+///
+/// Fails if the diffuse texture can't be loaded.
+fn convert_material(mtl: &tobj::Material, obj_dir: &Path) -> Result<Material, MeshError> {
+    let to_color = |rgb: [f32; 3]| Color::new(Vec3A::from(rgb));
+
+    let emission = mtl.unknown_param.get("Ke").and_then(|ke| {
+        let rgb: Vec<f32> = ke
+            .split_whitespace()
+            .filter_map(|c| c.parse().ok())
+            .collect();
+        match rgb[..] {
+            [r, g, b] if r.max(g).max(b) > 0.0 => Some(Vec3A::new(r, g, b)),
+            _ => None,
+        }
+    });
+
+    if let Some(emission) = emission {
+        // keep the color within 0..1, and move the intensity into the brightness
+        let brightness = emission.max_element();
+        return Ok(Material::DiffuseLight {
+            albedo: Arc::new(Color::new(emission / brightness)),
+            brightness,
+        });
+    }
+
+    let illum = mtl.illumination_model.unwrap_or(2);
+    if mtl.dissolve.unwrap_or(1.0) < 1.0 || matches!(illum, 4 | 6 | 7 | 9) {
+        return Ok(Material::Dielectric {
+            refract_index: mtl.optical_density.unwrap_or(1.5),
+        });
+    }
+
+    if matches!(illum, 3 | 5 | 8) {
+        // approximate roughness from the specular exponent
+        let shininess = mtl.shininess.unwrap_or(0.0).max(0.0);
+        return Ok(Material::Metal {
+            albedo: Arc::new(to_color(mtl.specular.unwrap_or([1.0; 3]))),
+            roughness: (2.0 / (shininess + 2.0)).sqrt(),
+        });
+    }
+
+    let albedo: Arc<dyn Texture> = match &mtl.diffuse_texture {
+        Some(texture_path) => {
+            let path = obj_dir.join(texture_path);
+            match ImageMap::load(path.clone()) {
+                Ok(image) => Arc::new(image),
+                Err(e) => return Err(MeshError::Texture(path, e)),
+            }
+        }
+        None => Arc::new(to_color(mtl.diffuse.unwrap_or([0.8; 3]))),
+    };
+    Ok(Material::Lambertian { albedo })
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        self.triangles.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<BoundingBox> {
+        self.triangles.bounding_box(time0, time1)
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Mesh {
+            path: materials.path(&self.file_path),
+            material: materials.name(&self.material)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_example_mesh() {
+        let fallback = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let mesh =
+            TriangleMesh::from_obj(PathBuf::from("resources/meshes/icosahedron.obj"), &fallback)
+                .unwrap();
+
+        let bbox = mesh.bounding_box(0.0, 1.0).unwrap();
+        assert!(bbox.min.cmpge(Vec3A::splat(-1.001)).all());
+        assert!(bbox.max.cmple(Vec3A::splat(1.001)).all());
+
+        // the gold material from the MTL file is used instead of the fallback
+        let ray = Ray::new(Vec3A::new(0.0, 0.0, 5.0), -Vec3A::Z, 0.0);
+        let rec = mesh.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!(rec.front_face);
+        assert!(matches!(rec.material.as_ref(), Material::Metal { .. }));
+
+        // described relative to the scene file's directory
+        let mut names = MaterialNames::new(Path::new("resources/scenes"));
+        match mesh.describe(&mut names) {
+            Some(ObjectDescription::Mesh { path, .. }) => {
+                assert_eq!(path, Path::new("../meshes/icosahedron.obj"))
+            }
+            desc => panic!("expected a mesh, got {:?}", desc),
+        }
+    }
+
+    #[test]
+    fn missing_texture_fails() {
+        let fallback = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let dir = std::env::temp_dir().join(format!("lustre-mesh-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("textured.mtl"),
+            "newmtl paint\nmap_Kd missing.png\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("textured.obj"),
+            "mtllib textured.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl paint\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        let result = TriangleMesh::from_obj(dir.join("textured.obj"), &fallback);
+        std::fs::remove_dir_all(&dir).unwrap();
+        match result {
+            Err(MeshError::Texture(path, _)) => assert!(path.ends_with("missing.png")),
+            Err(e) => panic!("expected a texture error, got {}", e),
+            Ok(_) => panic!("expected a texture error"),
+        }
+    }
+}
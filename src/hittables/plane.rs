@@ -0,0 +1,225 @@
+//! Implementations of flat objects: infinite planes and disks
+
+use std::sync::Arc;
+
+use glam::Vec3A;
+
+use crate::{
+    bounds::BoundingBox,
+    material::Material,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+    utils::geometry::{circle_extent, tangent_axes},
+};
+
+use super::{HitRecord, Hittable};
+
+/// Returns the distance along the ray to the plane through `point` with the given `normal`
+fn plane_t(ray: &Ray, point: Vec3A, normal: Vec3A) -> f32 {
+    (point - ray.origin).dot(normal) / ray.direction.dot(normal)
+}
+
+/// An infinite plane through a point
+///
+/// The plane has no bounding box, and is tested against every ray.
+/// Its u/v coordinates repeat every `tile_size` units, tiling textures across the plane.
+#[derive(Debug)]
+pub struct Plane {
+    point: Vec3A,
+    normal: Vec3A,
+    tile_size: f32,
+    u_axis: Vec3A,
+    v_axis: Vec3A,
+    pub material: Arc<Material>,
+}
+
+impl Plane {
+    /// Creates a new Plane through `point`, facing towards `normal`
+    pub fn new(point: Vec3A, normal: Vec3A, m: &Arc<Material>) -> Self {
+        let normal = normal.normalize();
+        let (u_axis, v_axis) = tangent_axes(normal);
+        Self {
+            point,
+            normal,
+            tile_size: 1.0,
+            u_axis,
+            v_axis,
+            material: Arc::clone(m),
+        }
+    }
+
+    /// Sets how many units a texture spans before repeating
+    pub fn with_tile_size(mut self, tile_size: f32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let t = plane_t(ray, self.point, self.normal);
+        // also rejects rays parallel to the plane, as t is then infinite or NaN
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let offset = (point - self.point) / self.tile_size;
+
+        let mut rec = HitRecord {
+            point,
+            normal: self.normal,
+            material: Arc::clone(&self.material),
+            t,
+            u: offset.dot(self.u_axis).rem_euclid(1.0),
+            v: offset.dot(self.v_axis).rem_euclid(1.0),
+            front_face: true,
+        };
+        rec.set_face_normal(ray, self.normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        None
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Plane {
+            point: self.point,
+            normal: self.normal,
+            tile_size: self.tile_size,
+            material: materials.name(&self.material)?,
+        })
+    }
+}
+
+/// A flat, circular disk
+///
+/// Textures are mapped onto the square enclosing the disk.
+#[derive(Debug)]
+pub struct Disk {
+    center: Vec3A,
+    normal: Vec3A,
+    radius: f32,
+    u_axis: Vec3A,
+    v_axis: Vec3A,
+    pub material: Arc<Material>,
+}
+
+impl Disk {
+    /// Creates a new Disk around `center`, facing towards `normal`
+    pub fn new(center: Vec3A, normal: Vec3A, radius: f32, m: &Arc<Material>) -> Self {
+        let normal = normal.normalize();
+        let (u_axis, v_axis) = tangent_axes(normal);
+        Self {
+            center,
+            normal,
+            radius,
+            u_axis,
+            v_axis,
+            material: Arc::clone(m),
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let t = plane_t(ray, self.center, self.normal);
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let offset = point - self.center;
+        if offset.length_squared() > self.radius * self.radius {
+            return None;
+        }
+
+        let mut rec = HitRecord {
+            point,
+            normal: self.normal,
+            material: Arc::clone(&self.material),
+            t,
+            u: 0.5 + 0.5 * offset.dot(self.u_axis) / self.radius,
+            v: 0.5 + 0.5 * offset.dot(self.v_axis) / self.radius,
+            front_face: true,
+        };
+        rec.set_face_normal(ray, self.normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        // padded in case the disk is axis-aligned
+        let extent = self.radius * circle_extent(self.normal) + 0.0001;
+        Some(BoundingBox::new(self.center - extent, self.center + extent))
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Disk {
+            center: self.center,
+            normal: self.normal,
+            radius: self.radius,
+            material: materials.name(&self.material)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_plane_and_disk() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let plane = Plane::new(Vec3A::ZERO, Vec3A::Y, &mat).with_tile_size(2.0);
+        let disk = Disk::new(Vec3A::ZERO, Vec3A::Y, 1.0, &mat);
+
+        // from above, hitting the front
+        let ray = Ray::new(Vec3A::new(0.5, 2.0, -0.5), -Vec3A::Y, 0.0);
+        let rec = plane.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-4);
+        assert!(rec.front_face);
+        assert!(rec.normal.abs_diff_eq(Vec3A::Y, 1e-4));
+        let rec = disk.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!(rec.front_face);
+        // the disk maps its enclosing square onto u/v, with u along X and v along -Z
+        assert!((rec.u - 0.75).abs() < 1e-4 && (rec.v - 0.75).abs() < 1e-4);
+
+        // from below, hitting the back
+        let ray = Ray::new(Vec3A::new(0.5, -2.0, -0.5), Vec3A::Y, 0.0);
+        let rec = plane.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!(!rec.front_face);
+        assert!(rec.normal.abs_diff_eq(-Vec3A::Y, 1e-4));
+        assert!(!disk.hit(&ray, 0.001, f32::INFINITY).unwrap().front_face);
+
+        // parallel, pointing away, beyond t_max, or outside the disk's radius
+        let parallel = Ray::new(Vec3A::new(0.0, 1.0, 0.0), Vec3A::X, 0.0);
+        assert!(plane.hit(&parallel, 0.001, f32::INFINITY).is_none());
+        let away = Ray::new(Vec3A::new(0.0, 1.0, 0.0), Vec3A::Y, 0.0);
+        assert!(plane.hit(&away, 0.001, f32::INFINITY).is_none());
+        let down = Ray::new(Vec3A::new(0.0, 1.0, 0.0), -Vec3A::Y, 0.0);
+        assert!(plane.hit(&down, 0.001, 0.5).is_none());
+        let outside = Ray::new(Vec3A::new(1.5, 1.0, 0.0), -Vec3A::Y, 0.0);
+        assert!(plane.hit(&outside, 0.001, f32::INFINITY).is_some());
+        assert!(disk.hit(&outside, 0.001, f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn tiles_plane_coordinates() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let plane = Plane::new(Vec3A::ZERO, Vec3A::Y, &mat).with_tile_size(2.0);
+        let uv_at = |x: f32, z: f32| {
+            let ray = Ray::new(Vec3A::new(x, 1.0, z), -Vec3A::Y, 0.0);
+            let rec = plane.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            (rec.u, rec.v)
+        };
+
+        // u along X and v along -Z, repeating every 2 units, also on the negative side
+        let (u, v) = uv_at(0.5, -1.0);
+        assert!((u - 0.25).abs() < 1e-4 && (v - 0.5).abs() < 1e-4);
+        let (u, v) = uv_at(4.5, -5.0);
+        assert!((u - 0.25).abs() < 1e-4 && (v - 0.5).abs() < 1e-4);
+        let (u, v) = uv_at(-1.5, 1.0);
+        assert!((u - 0.25).abs() < 1e-4 && (v - 0.5).abs() < 1e-4);
+    }
+}
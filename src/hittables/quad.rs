@@ -3,8 +3,14 @@
 use std::sync::Arc;
 
 use glam::{Vec2, Vec3A};
+use rand::{Rng, RngCore};
 
-use crate::{bounds::BoundingBox, material::Material};
+use crate::{
+    bounds::BoundingBox,
+    material::Material,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+};
 
 use super::{HitRecord, Hittable};
 
@@ -116,6 +122,14 @@ impl Quad {
     fn cross(a: Vec2, b: Vec2) -> f32 {
         a.x * b.y - a.y * b.x
     }
+
+    /// Returns the areas of the two triangles on either side of the p0-p2 diagonal
+    fn triangle_areas(&self) -> (f32, f32) {
+        let diagonal = self.p2 - self.p0;
+        let area0 = (self.p1 - self.p0).cross(diagonal).length() / 2.0;
+        let area1 = diagonal.cross(self.p3 - self.p0).length() / 2.0;
+        (area0, area1)
+    }
 }
 
 impl Hittable for Quad {
@@ -126,7 +140,7 @@ impl Hittable for Quad {
         Some(BoundingBox::new(min, max))
     }
 
-    fn hit(&self, ray: &crate::ray::Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
         // see https://www.shadertoy.com/view/XtlBDs
         // 0--b--3
         // |\
@@ -217,4 +231,52 @@ impl Hittable for Quad {
 
         Some(rec)
     }
+
+    fn is_light(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    /// Samples a point uniformly over the area of the quad, which is assumed to be flat and convex
+    fn sample_direction(&self, origin: Vec3A, rng: &mut dyn RngCore) -> Option<Vec3A> {
+        // pick one of the triangles by area, then a point within it
+        let (area0, area1) = self.triangle_areas();
+        let (a, b) = if rng.gen::<f32>() * (area0 + area1) < area0 {
+            (self.p1, self.p2)
+        } else {
+            (self.p2, self.p3)
+        };
+        let (mut s, mut t): (f32, f32) = (rng.gen(), rng.gen());
+        if s + t > 1.0 {
+            // fold the far half of the parallelogram back onto the triangle
+            s = 1.0 - s;
+            t = 1.0 - t;
+        }
+
+        let point = self.p0 + s * (a - self.p0) + t * (b - self.p0);
+        Some(point - origin)
+    }
+
+    fn pdf_value(&self, origin: Vec3A, direction: Vec3A) -> f32 {
+        let ray = Ray::new(origin, direction, 0.0);
+        match self.hit(&ray, 0.001, f32::INFINITY) {
+            // convert the area density to solid angle, as seen from the origin
+            Some(rec) => {
+                let (area0, area1) = self.triangle_areas();
+                let distance_sq = rec.t * rec.t * direction.length_squared();
+                let cosine = direction.normalize().dot(rec.normal).abs();
+                distance_sq / (cosine * (area0 + area1))
+            }
+            None => 0.0,
+        }
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Quad {
+            p0: self.p0,
+            p1: self.p1,
+            p2: self.p2,
+            p3: self.p3,
+            material: materials.name(&self.material)?,
+        })
+    }
 }
@@ -3,10 +3,24 @@ use std::sync::Arc;
 
 use glam::Vec3A;
 
-use crate::{bounds::BoundingBox, material::Material};
+use crate::{
+    bounds::BoundingBox,
+    material::Material,
+    scenes::{MaterialNames, ObjectDescription},
+};
 
 use super::{HitRecord, Hittable, Quad};
 
+/// Outward normals of the sides of a [QuadBox], in the order they are created
+const OUTWARD_NORMALS: [Vec3A; 6] = [
+    Vec3A::Z,
+    Vec3A::NEG_Z,
+    Vec3A::Y,
+    Vec3A::NEG_Y,
+    Vec3A::X,
+    Vec3A::NEG_X,
+];
+
 /// A box defined by a set of [Quad] faces
 #[derive(Debug)]
 pub struct QuadBox {
@@ -66,13 +80,26 @@ impl Hittable for QuadBox {
         let mut rec = None;
         let mut t_closest = t_max;
 
-        for hittable in self.sides.iter() {
+        for (hittable, outward_n) in self.sides.iter().zip(OUTWARD_NORMALS) {
             let hit_result = hittable.hit(ray, t_min, t_closest);
             if let Some(HitRecord { t, .. }) = hit_result {
                 t_closest = t;
-                rec = hit_result;
+                rec = hit_result.map(|rec| (rec, outward_n));
             }
         }
-        rec
+
+        // the quads don't all face outwards, so front faces are decided by the box instead
+        let (mut rec, outward_n) = rec?;
+        rec.set_face_normal(ray, outward_n);
+        Some(rec)
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        // all sides share the same material
+        Some(ObjectDescription::QuadBox {
+            min: self.min,
+            max: self.max,
+            material: materials.name(&self.sides[0].material)?,
+        })
     }
 }
@@ -0,0 +1,317 @@
+//! Objects defined by signed distance functions, intersected by sphere tracing
+
+use std::sync::Arc;
+
+use glam::Vec3A;
+
+use crate::{
+    bounds::BoundingBox,
+    material::Material,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription, SdfDescription},
+};
+
+use super::{HitRecord, Hittable};
+
+/// A signed distance function
+///
+/// Returns the distance from a point to the closest surface,
+/// negative for points inside the shape.
+/// Distances may be underestimated, but never overestimated, or sphere tracing can step through the surface.
+pub trait Sdf: Send + Sync {
+    fn distance(&self, point: Vec3A) -> f32;
+
+    /// Returns a description of the shape, as used by scene files
+    ///
+    /// Returns `None` if the shape can't be described, which is the default.
+    fn describe(&self) -> Option<SdfDescription> {
+        None
+    }
+}
+
+impl<F> Sdf for F
+where
+    F: Fn(Vec3A) -> f32 + Send + Sync,
+{
+    fn distance(&self, point: Vec3A) -> f32 {
+        self(point)
+    }
+}
+
+/// A sphere around a center
+#[derive(Debug, Clone, Copy)]
+pub struct SdfSphere {
+    pub center: Vec3A,
+    pub radius: f32,
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, point: Vec3A) -> f32 {
+        (point - self.center).length() - self.radius
+    }
+
+    fn describe(&self) -> Option<SdfDescription> {
+        Some(SdfDescription::Sphere {
+            center: self.center,
+            radius: self.radius,
+        })
+    }
+}
+
+/// A box with its edges rounded off by `radius`
+#[derive(Debug, Clone, Copy)]
+pub struct RoundedBox {
+    pub center: Vec3A,
+    /// Half the size of the box along each axis, including the rounding
+    pub half_extents: Vec3A,
+    pub radius: f32,
+}
+
+impl Sdf for RoundedBox {
+    fn distance(&self, point: Vec3A) -> f32 {
+        let q = (point - self.center).abs() - self.half_extents + self.radius;
+        q.max(Vec3A::ZERO).length() + q.max_element().min(0.0) - self.radius
+    }
+
+    fn describe(&self) -> Option<SdfDescription> {
+        Some(SdfDescription::RoundedBox {
+            center: self.center,
+            half_extents: self.half_extents,
+            radius: self.radius,
+        })
+    }
+}
+
+/// A union of two shapes, blended together over a distance of `smoothness`
+pub struct SmoothUnion {
+    pub a: Arc<dyn Sdf>,
+    pub b: Arc<dyn Sdf>,
+    pub smoothness: f32,
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, point: Vec3A) -> f32 {
+        let (d1, d2) = (self.a.distance(point), self.b.distance(point));
+        let h = (0.5 + 0.5 * (d2 - d1) / self.smoothness).clamp(0.0, 1.0);
+        d2 + (d1 - d2) * h - self.smoothness * h * (1.0 - h)
+    }
+
+    fn describe(&self) -> Option<SdfDescription> {
+        Some(SdfDescription::SmoothUnion {
+            a: Box::new(self.a.describe()?),
+            b: Box::new(self.b.describe()?),
+            smoothness: self.smoothness,
+        })
+    }
+}
+
+/// The [Mandelbulb](https://en.wikipedia.org/wiki/Mandelbulb) fractal
+///
+/// At a `scale` of 1, the fractal fits within a sphere of radius 1.2 around its center.
+#[derive(Debug, Clone, Copy)]
+pub struct Mandelbulb {
+    pub center: Vec3A,
+    pub scale: f32,
+    pub power: f32,
+    pub iterations: u32,
+}
+
+impl Sdf for Mandelbulb {
+    fn distance(&self, point: Vec3A) -> f32 {
+        let c = (point - self.center) / self.scale;
+        let mut z = c;
+        let mut dr = 1.0;
+        let mut r = z.length();
+
+        for _ in 0..self.iterations {
+            if r > 2.0 {
+                break;
+            }
+            // raise z to the power in spherical coordinates, keeping track of the derivative
+            let theta = (z.z / r).acos() * self.power;
+            let phi = z.y.atan2(z.x) * self.power;
+            dr = r.powf(self.power - 1.0) * self.power * dr + 1.0;
+            z = r.powf(self.power)
+                * Vec3A::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                )
+                + c;
+            r = z.length();
+        }
+
+        0.5 * r.ln() * r / dr * self.scale
+    }
+
+    fn describe(&self) -> Option<SdfDescription> {
+        Some(SdfDescription::Mandelbulb {
+            center: self.center,
+            scale: self.scale,
+            power: self.power,
+            iterations: self.iterations,
+        })
+    }
+}
+
+/// An object whose surface is where a [Sdf] is zero
+///
+/// Rays are intersected by sphere tracing within the object's bounding box,
+/// and normals are estimated from the gradient of the distance function.
+/// The object has no texture coordinates.
+pub struct SdfObject {
+    sdf: Arc<dyn Sdf>,
+    bbox: BoundingBox,
+    max_steps: u32,
+    epsilon: f32,
+    pub material: Arc<Material>,
+}
+
+impl SdfObject {
+    /// Creates a new SdfObject, with a box enclosing every point where `sdf` is negative
+    pub fn new(sdf: &Arc<dyn Sdf>, bbox: BoundingBox, m: &Arc<Material>) -> Self {
+        Self {
+            sdf: Arc::clone(sdf),
+            bbox,
+            max_steps: 256,
+            epsilon: 0.0001,
+            material: Arc::clone(m),
+        }
+    }
+
+    /// Sets how many steps a ray takes before giving up on hitting the surface
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Sets how close to the surface counts as hitting it
+    pub fn with_epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// Estimates the outward normal at a point, using the tetrahedron technique
+    fn normal(&self, point: Vec3A) -> Vec3A {
+        const CORNERS: [Vec3A; 4] = [
+            Vec3A::new(1.0, -1.0, -1.0),
+            Vec3A::new(-1.0, -1.0, 1.0),
+            Vec3A::new(-1.0, 1.0, -1.0),
+            Vec3A::new(1.0, 1.0, 1.0),
+        ];
+        CORNERS
+            .iter()
+            .map(|&k| k * self.sdf.distance(point + k * self.epsilon))
+            .fold(Vec3A::ZERO, |acc, v| acc + v)
+            .normalize()
+    }
+}
+
+impl Hittable for SdfObject {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let (t_start, t_end) = self.bbox.clip(ray, t_min, t_max)?;
+        // distances are in world units, while t is in multiples of the ray direction
+        let inverse_speed = ray.direction.length().recip();
+
+        let mut t = t_start;
+        // which side of the surface the ray is marching on, once it is clear of the surface.
+        // Rays entering the bounding box start outside, even where the surface touches the box.
+        let mut side = (t_start > t_min).then_some(1.0);
+        for _ in 0..self.max_steps {
+            if t > t_end {
+                return None;
+            }
+            let distance = self.sdf.distance(ray.at(t));
+
+            let hit = match side {
+                // step off the surface a ray may have started on, e.g. after scattering
+                None if distance.abs() < self.epsilon => {
+                    t += self.epsilon * inverse_speed;
+                    continue;
+                }
+                None => {
+                    side = Some(distance.signum());
+                    false
+                }
+                // also stop when overshooting the surface entirely
+                Some(side) => distance * side < self.epsilon,
+            };
+
+            if hit {
+                let point = ray.at(t);
+                let outward_n = self.normal(point);
+                let mut rec = HitRecord {
+                    point,
+                    normal: outward_n,
+                    material: Arc::clone(&self.material),
+                    t,
+                    u: 0.0,
+                    v: 0.0,
+                    front_face: true,
+                };
+                rec.set_face_normal(ray, outward_n);
+                return Some(rec);
+            }
+            t += distance.abs() * inverse_speed;
+        }
+        None
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        Some(self.bbox)
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Sdf {
+            sdf: self.sdf.describe()?,
+            min: self.bbox.min,
+            max: self.bbox.max,
+            max_steps: self.max_steps,
+            epsilon: self.epsilon,
+            material: materials.name(&self.material)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittables::Sphere;
+
+    #[test]
+    fn matches_sphere() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let sphere = Sphere::new(Vec3A::ZERO, 1.0, &mat);
+        let sdf: Arc<dyn Sdf> = Arc::new(SdfSphere {
+            center: Vec3A::ZERO,
+            radius: 1.0,
+        });
+        let bbox = BoundingBox::new(Vec3A::splat(-1.0), Vec3A::splat(1.0));
+        let sdf_sphere = SdfObject::new(&sdf, bbox, &mat);
+
+        let rays = [
+            // from outside, with an unnormalized direction
+            Ray::new(Vec3A::new(0.3, 0.2, 5.0), Vec3A::new(0.0, 0.0, -2.0), 0.0),
+            // from inside
+            Ray::new(Vec3A::new(0.1, 0.0, 0.0), Vec3A::new(1.0, 1.0, 0.0), 0.0),
+            // from the surface, going through the sphere
+            Ray::new(Vec3A::new(0.0, 0.0, 1.0), Vec3A::new(0.2, 0.1, -1.0), 0.0),
+        ];
+        for ray in rays {
+            let expected = sphere.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            let rec = sdf_sphere.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            assert!(
+                (rec.t - expected.t).abs() < 1e-3,
+                "{} != {}",
+                rec.t,
+                expected.t
+            );
+            assert_eq!(rec.front_face, expected.front_face);
+            assert!(rec.normal.abs_diff_eq(expected.normal, 1e-2));
+        }
+
+        // missing the sphere, but not its bounding box
+        let ray = Ray::new(Vec3A::new(0.95, 0.95, 5.0), -Vec3A::Z, 0.0);
+        assert!(sdf_sphere.hit(&ray, 0.001, f32::INFINITY).is_none());
+    }
+}
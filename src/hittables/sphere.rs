@@ -6,12 +6,15 @@ use std::{
 };
 
 use glam::Vec3A;
+use rand::{Rng, RngCore};
 
 use crate::{
     bounds::BoundingBox,
     hittables::{HitRecord, Hittable},
     material::Material,
     ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+    utils::geometry::tangent_axes,
 };
 
 /// A Sphere object
@@ -40,6 +43,18 @@ impl Sphere {
         let v = theta / PI;
         (u, v)
     }
+
+    /// Returns `1 - cos` of the angle between the center and the edge of the sphere, as seen from `origin`
+    ///
+    /// Returns `None` if the origin is inside the sphere.
+    fn cone_height(&self, origin: Vec3A) -> Option<f32> {
+        let ratio = self.radius * self.radius / (self.center - origin).length_squared();
+        if ratio >= 1.0 {
+            return None;
+        }
+        // equal to 1 - sqrt(1 - ratio), without losing precision for far away spheres
+        Some(ratio / (1.0 + (1.0 - ratio).sqrt()))
+    }
 }
 
 impl Hittable for Sphere {
@@ -94,6 +109,40 @@ impl Hittable for Sphere {
             self.center + Vec3A::splat(self.radius),
         ))
     }
+
+    fn is_light(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    /// Samples a direction uniformly within the cone of directions hitting the sphere
+    fn sample_direction(&self, origin: Vec3A, rng: &mut dyn RngCore) -> Option<Vec3A> {
+        let height = self.cone_height(origin)?;
+        let cos_theta = 1.0 - rng.gen::<f32>() * height;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = TAU * rng.gen::<f32>();
+
+        let w_axis = (self.center - origin).normalize();
+        let (u_axis, v_axis) = tangent_axes(w_axis);
+        Some(sin_theta * (phi.cos() * u_axis + phi.sin() * v_axis) + cos_theta * w_axis)
+    }
+
+    fn pdf_value(&self, origin: Vec3A, direction: Vec3A) -> f32 {
+        let ray = Ray::new(origin, direction, 0.0);
+        match self.cone_height(origin) {
+            Some(height) if self.hit(&ray, 0.001, f32::INFINITY).is_some() => {
+                (TAU * height).recip()
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Sphere {
+            center: self.center,
+            radius: self.radius,
+            material: materials.name(&self.material)?,
+        })
+    }
 }
 
 /// Like [Sphere], but it moves.
@@ -203,4 +252,15 @@ impl Hittable for MovingSphere {
 
         Some(box0.union(&box1))
     }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::MovingSphere {
+            center0: self.center0,
+            center1: self.center1,
+            time0: self.time0,
+            time1: self.time1,
+            radius: self.radius,
+            material: materials.name(&self.material)?,
+        })
+    }
 }
@@ -0,0 +1,147 @@
+//! Torus implementation
+
+use std::{
+    f32::consts::{PI, TAU},
+    sync::Arc,
+};
+
+use glam::{DVec3, Vec3A};
+
+use crate::{
+    bounds::BoundingBox,
+    material::Material,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+    utils::polynomial::solve_quartic,
+};
+
+use super::{HitRecord, Hittable};
+
+/// A torus lying in the XZ plane, around the Y axis through its center
+///
+/// Use a [Transform](super::Transform) for other orientations.
+#[derive(Debug)]
+pub struct Torus {
+    center: Vec3A,
+    /// Distance from the center to the middle of the tube
+    major_radius: f32,
+    /// Radius of the tube
+    minor_radius: f32,
+    pub material: Arc<Material>,
+}
+
+impl Torus {
+    /// Creates a new Torus.
+    pub fn new(center: Vec3A, major_radius: f32, minor_radius: f32, m: &Arc<Material>) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+            material: Arc::clone(m),
+        }
+    }
+
+    /// Returns the uv surface coordinates for a point relative to the torus' center
+    fn surface_coords(&self, point: Vec3A) -> (f32, f32) {
+        // u goes around the Y axis, v goes around the tube
+        let phi = point.z.atan2(point.x);
+        let ring_dist = (point.x * point.x + point.z * point.z).sqrt() - self.major_radius;
+        let theta = point.y.atan2(ring_dist);
+        ((phi + PI) / TAU, (theta + PI) / TAU)
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        // solve in double precision with a unit direction,
+        // starting from the point on the ray closest to the center to keep coefficients small
+        let dir_len = ray.direction.length() as f64;
+        let dir = ray.direction.as_dvec3() / dir_len;
+        let origin = (ray.origin - self.center).as_dvec3();
+        let t_offset = -origin.dot(dir);
+        let origin: DVec3 = origin + t_offset * dir;
+
+        let sq_major = (self.major_radius as f64).powi(2);
+        let sq_minor = (self.minor_radius as f64).powi(2);
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (p.x^2 + p.z^2), for p = origin + s * dir
+        let n = origin.dot(dir);
+        let k = origin.length_squared() + sq_major - sq_minor;
+        let planar_dd = dir.x * dir.x + dir.z * dir.z;
+        let planar_od = origin.x * dir.x + origin.z * dir.z;
+        let planar_oo = origin.x * origin.x + origin.z * origin.z;
+
+        let roots = solve_quartic(
+            4.0 * n,
+            4.0 * n * n + 2.0 * k - 4.0 * sq_major * planar_dd,
+            4.0 * n * k - 8.0 * sq_major * planar_od,
+            k * k - 4.0 * sq_major * planar_oo,
+        );
+
+        let t = roots
+            .iter()
+            .map(|s| ((s + t_offset) / dir_len) as f32)
+            .find(|t| (t_min..=t_max).contains(t))?;
+
+        let point = ray.at(t);
+        let local = point - self.center;
+
+        // the normal points away from the closest point on the tube's center circle
+        let ring_point = Vec3A::new(local.x, 0.0, local.z).normalize_or_zero() * self.major_radius;
+        let outward_n = (local - ring_point).normalize();
+        let (u, v) = self.surface_coords(local);
+
+        let mut rec = HitRecord {
+            point,
+            normal: outward_n,
+            material: Arc::clone(&self.material),
+            t,
+            u,
+            v,
+            front_face: true,
+        };
+        rec.set_face_normal(ray, outward_n);
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        let outer = self.major_radius + self.minor_radius;
+        let extent = Vec3A::new(outer, self.minor_radius, outer);
+        Some(BoundingBox::new(self.center - extent, self.center + extent))
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Torus {
+            center: self.center,
+            major_radius: self.major_radius,
+            minor_radius: self.minor_radius,
+            material: materials.name(&self.material)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_outer_and_inner_sides() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let torus = Torus::new(Vec3A::ZERO, 2.0, 0.5, &mat);
+
+        // through the tube on one side, the hole, and then the other side
+        let ray = Ray::new(Vec3A::new(-10.0, 0.0, 0.0), Vec3A::X, 0.0);
+        let rec = torus.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 7.5).abs() < 1e-4);
+        assert!(rec.normal.abs_diff_eq(-Vec3A::X, 1e-4));
+        let rec = torus.hit(&ray, 7.6, f32::INFINITY).unwrap();
+        assert!((rec.t - 8.5).abs() < 1e-4);
+        assert!(!rec.front_face);
+        let rec = torus.hit(&ray, 8.6, f32::INFINITY).unwrap();
+        assert!((rec.t - 11.5).abs() < 1e-4);
+
+        // straight down through the hole
+        let ray = Ray::new(Vec3A::new(0.0, 10.0, 0.0), -Vec3A::Y, 0.0);
+        assert!(torus.hit(&ray, 0.001, f32::INFINITY).is_none());
+    }
+}
@@ -2,16 +2,66 @@
 
 use std::sync::Arc;
 
-use glam::{Affine3A, Vec3};
+use glam::{Affine3A, Mat3A, Vec3};
+use serde::{Deserialize, Serialize};
 
-use super::Hittable;
+use crate::{
+    bounds::BoundingBox,
+    material::Material,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+};
+
+use super::{HitRecord, Hittable};
 
 /// A hittable undergoes a transform before and after being hit.
 pub struct Transform {
     matrix: Affine3A,
+    /// Inverse of `matrix`, taking rays into the object's space
+    inverse: Affine3A,
+    /// Inverse-transpose of `matrix`, taking normals out of the object's space
+    normal_matrix: Mat3A,
+    /// The operations making up the transform, in order of application
+    operations: Vec<TransformOperation>,
     object: Arc<dyn Hittable>,
 }
 
+/// A single operation making up a [Transform]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum TransformOperation {
+    Scale(Vec3),
+    /// A rotation around an `axis`, of `degrees`
+    Rotate {
+        axis: Vec3,
+        degrees: f32,
+    },
+    Translate(Vec3),
+    LookAt {
+        camera_pos: Vec3,
+        focal_point: Vec3,
+        up_dir: Vec3,
+    },
+}
+
+impl TransformOperation {
+    /// Returns the affine transform performing this operation
+    fn matrix(&self) -> Affine3A {
+        match *self {
+            TransformOperation::Scale(scale) => Affine3A::from_scale(scale),
+            TransformOperation::Rotate { axis, degrees } => {
+                Affine3A::from_axis_angle(axis, degrees.to_radians())
+            }
+            TransformOperation::Translate(translation) => Affine3A::from_translation(translation),
+            TransformOperation::LookAt {
+                camera_pos,
+                focal_point,
+                up_dir,
+            } => Affine3A::look_at_rh(camera_pos, focal_point, up_dir),
+        }
+    }
+}
+
 #[allow(dead_code/* , reason = "Exposing Affine3A constructors" */)]
 impl Transform {
     // creators
@@ -20,32 +70,26 @@ impl Transform {
     pub fn new(o: &Arc<dyn Hittable>) -> Self {
         Self {
             matrix: Affine3A::IDENTITY,
+            inverse: Affine3A::IDENTITY,
+            normal_matrix: Mat3A::IDENTITY,
+            operations: vec![],
             object: Arc::clone(o),
         }
     }
 
     /// Creates an affine transform that changes the size of the object.
     pub fn from_scale_factor(scale: Vec3, o: &Arc<dyn Hittable>) -> Self {
-        Self {
-            matrix: Affine3A::from_scale(scale),
-            object: Arc::clone(o),
-        }
+        Self::new(o).with_scale_factor(scale).finalize()
     }
 
     /// Creates an affine transform containing a 3D rotation around an `axis`, of `angle` (in radians).
     pub fn from_axis_angle(axis: Vec3, angle: f32, o: &Arc<dyn Hittable>) -> Self {
-        Self {
-            matrix: Affine3A::from_axis_angle(axis, angle),
-            object: Arc::clone(o),
-        }
+        Self::new(o).with_axis_angle(axis, angle).finalize()
     }
 
     /// Creates an affine transform from the given 3D `translation`.
     pub fn from_translation(translation: Vec3, o: &Arc<dyn Hittable>) -> Self {
-        Self {
-            matrix: Affine3A::from_translation(translation),
-            object: Arc::clone(o),
-        }
+        Self::new(o).with_translation(translation).finalize()
     }
 
     /// Creates a view transform using a camera position, a focal point, and an up direction.
@@ -55,71 +99,283 @@ impl Transform {
         up_dir: Vec3,
         o: &Arc<dyn Hittable>,
     ) -> Self {
-        Self {
-            matrix: Affine3A::look_at_rh(camera_pos, focal_point, up_dir),
-            object: Arc::clone(o),
-        }
+        Self::new(o)
+            .with_operation(TransformOperation::LookAt {
+                camera_pos,
+                focal_point,
+                up_dir,
+            })
+            .finalize()
     }
 
     // builders
 
     /// Adds a scaling factor to the existing affine transform
     pub fn with_scale_factor(&mut self, scale: Vec3) -> &mut Self {
-        self.matrix = Affine3A::from_scale(scale) * self.matrix;
-        self
+        self.with_operation(TransformOperation::Scale(scale))
     }
 
-    /// Adds a rotation based on the axis and angle to the existing affine transform
+    /// Adds a rotation based on the axis and angle (in radians) to the existing affine transform
     pub fn with_axis_angle(&mut self, axis: Vec3, angle: f32) -> &mut Self {
-        self.matrix = Affine3A::from_axis_angle(axis, angle) * self.matrix;
+        // use the given angle as-is, rather than going through a conversion to degrees
+        self.set_matrix(Affine3A::from_axis_angle(axis, angle) * self.matrix);
+        self.operations.push(TransformOperation::Rotate {
+            axis,
+            degrees: angle.to_degrees(),
+        });
         self
     }
 
     /// Adds a translation to the existing affine transform
     pub fn with_translation(&mut self, translation: Vec3) -> &mut Self {
-        self.matrix = Affine3A::from_translation(translation) * self.matrix;
+        self.with_operation(TransformOperation::Translate(translation))
+    }
+
+    /// Adds the given operation to the existing affine transform
+    pub fn with_operation(&mut self, operation: TransformOperation) -> &mut Self {
+        self.set_matrix(operation.matrix() * self.matrix);
+        self.operations.push(operation);
         self
     }
 
+    /// Replaces the affine transform, updating the matrices derived from it
+    fn set_matrix(&mut self, matrix: Affine3A) {
+        self.matrix = matrix;
+        self.inverse = matrix.inverse();
+        self.normal_matrix = matrix.matrix3.inverse().transpose();
+    }
+
     pub fn finalize(&mut self) -> Self {
         Self {
             matrix: self.matrix,
+            inverse: self.inverse,
+            normal_matrix: self.normal_matrix,
+            operations: self.operations.clone(),
             object: self.object.to_owned(),
         }
     }
 }
 
 impl Hittable for Transform {
-    fn hit(&self, ray: &crate::ray::Ray, t_min: f32, t_max: f32) -> Option<super::HitRecord> {
-        let transformed_ray = crate::ray::Ray::new(
-            self.matrix.inverse().transform_point3a(ray.origin),
-            self.matrix.inverse().transform_vector3a(ray.direction),
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        // the direction isn't normalized, so distances along both rays are the same
+        let transformed_ray = Ray::new(
+            self.inverse.transform_point3a(ray.origin),
+            self.inverse.transform_vector3a(ray.direction),
             ray.time,
         );
 
-        match self.object.hit(&transformed_ray, t_min, t_max) {
-            Some(rec) => {
-                let mut transformed_rec = super::HitRecord {
-                    point: self.matrix.transform_point3a(rec.point),
-                    normal: self.matrix.transform_vector3a(rec.normal),
-                    ..rec
-                };
-                transformed_rec.set_face_normal(&transformed_ray, rec.normal);
-                Some(transformed_rec)
-            }
-            None => None,
+        // the normal already faces against the ray, which the inverse-transpose preserves
+        let rec = self.object.hit(&transformed_ray, t_min, t_max)?;
+        Some(HitRecord {
+            point: self.matrix.transform_point3a(rec.point),
+            normal: (self.normal_matrix * rec.normal).normalize(),
+            ..rec
+        })
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<BoundingBox> {
+        let original_box = self.object.bounding_box(time0, time1)?;
+
+        // rotations can move any corner of the original box to the outside
+        original_box
+            .corners()
+            .map(|corner| self.matrix.transform_point3a(corner))
+            .into_iter()
+            .map(|corner| BoundingBox::new(corner, corner))
+            .reduce(|acc, corner| acc.union(&corner))
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        // a leading scale, rotation and translation fit the description's own fields
+        let mut operations = self.operations.as_slice();
+        let (mut scale, mut rotation, mut translation) = (Vec3::ONE, (Vec3::Y, 0.0), Vec3::ZERO);
+        if let [TransformOperation::Scale(s), rest @ ..] = operations {
+            scale = *s;
+            operations = rest;
         }
+        if let [TransformOperation::Rotate { axis, degrees }, rest @ ..] = operations {
+            rotation = (*axis, *degrees);
+            operations = rest;
+        }
+        if let [TransformOperation::Translate(t), rest @ ..] = operations {
+            translation = *t;
+            operations = rest;
+        }
+
+        Some(ObjectDescription::Transform {
+            object: Box::new(self.object.describe(materials)?),
+            scale,
+            rotation,
+            translation,
+            operations: operations.to_vec(),
+        })
     }
+}
 
-    fn bounding_box(&self, time0: f32, time1: f32) -> Option<crate::bounds::BoundingBox> {
-        let original_box = self.object.bounding_box(time0, time1);
-        match original_box {
-            Some(original_box) => {
-                let new_min = self.matrix.transform_point3a(original_box.min);
-                let new_max = self.matrix.transform_point3a(original_box.max);
-                Some(crate::bounds::BoundingBox::new(new_min, new_max))
-            }
+/// A transformed copy of a shared prototype object, optionally with a material of its own
+///
+/// Many instances can share the same prototype, such as a mesh or a BVH,
+/// while only storing their own transform.
+pub struct Instance {
+    transform: Transform,
+    /// Replaces the material of every surface of the prototype, if set
+    material: Option<Arc<Material>>,
+}
+
+impl Instance {
+    /// Creates an untransformed instance of `prototype`, keeping its materials
+    pub fn new(prototype: &Arc<dyn Hittable>) -> Self {
+        Self {
+            transform: Transform::new(prototype),
+            material: None,
+        }
+    }
+
+    /// Adds the given operation to the instance's transform
+    pub fn with_operation(mut self, operation: TransformOperation) -> Self {
+        self.transform.with_operation(operation);
+        self
+    }
+
+    /// Uses the given material for the instance, instead of the prototype's materials
+    pub fn with_material(mut self, m: &Arc<Material>) -> Self {
+        self.material = Some(Arc::clone(m));
+        self
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let rec = self.transform.hit(ray, t_min, t_max)?;
+        match &self.material {
+            Some(material) => Some(HitRecord {
+                material: Arc::clone(material),
+                ..rec
+            }),
+            None => Some(rec),
+        }
+    }
+
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<BoundingBox> {
+        self.transform.bounding_box(time0, time1)
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        let material = match &self.material {
+            Some(material) => Some(materials.name(material)?),
             None => None,
+        };
+        Some(ObjectDescription::Instance {
+            prototype: materials.prototype(&self.transform.object)?,
+            operations: self.transform.operations.clone(),
+            material,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3A;
+
+    use super::*;
+    use crate::hittables::{QuadBox, Sphere};
+
+    #[test]
+    fn rotated_quadbox() {
+        // the tall box of the Cornell box scene
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let size = Vec3A::new(165.0, 330.0, 165.0);
+        let quadbox: Arc<dyn Hittable> = QuadBox::new(Vec3A::ZERO, size, &mat).wrap();
+        let transform = Transform::new(&quadbox)
+            .with_axis_angle(Vec3::Y, 15.0f32.to_radians())
+            .with_translation(Vec3::new(265.0, 0.0, 295.0))
+            .finalize();
+
+        // the box must contain every corner, and be no larger than needed
+        let bbox = transform.bounding_box(0.0, 1.0).unwrap();
+        let (sin, cos) = 15.0f32.to_radians().sin_cos();
+        let extent = Vec3A::new(165.0 * (sin + cos), 330.0, 165.0 * (sin + cos));
+        assert!((bbox.max - bbox.min).abs_diff_eq(extent, 1e-3));
+        for corner in BoundingBox::new(Vec3A::ZERO, size).corners() {
+            let point = transform.matrix.transform_point3a(corner);
+            assert!(point.cmpge(bbox.min - 1e-3).all() && point.cmple(bbox.max + 1e-3).all());
+        }
+
+        // rays towards the center of the box hit its faces head on, from outside
+        let center = transform.matrix.transform_point3a(size / 2.0);
+        let faces = [
+            Vec3A::new(cos, 0.0, -sin),
+            Vec3A::new(sin, 0.0, cos),
+            Vec3A::Y,
+        ];
+        for face in faces.into_iter().flat_map(|face| [face, -face]) {
+            let ray = Ray::new(center + 1000.0 * face, -face, 0.0);
+            let rec = transform.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            assert!(rec.front_face);
+            assert!(
+                rec.normal.abs_diff_eq(face, 1e-4),
+                "{} != {}",
+                rec.normal,
+                face
+            );
         }
     }
+
+    #[test]
+    fn scaled_sphere_normals() {
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let sphere: Arc<dyn Hittable> = Sphere::new(Vec3A::ZERO, 1.0, &mat).wrap();
+        let ellipsoid = Transform::from_scale_factor(Vec3::new(4.0, 1.0, 1.0), &sphere);
+
+        // the normal of the ellipsoid x^2/16 + y^2 = 1 is along (x/16, y, 0)
+        let ray = Ray::new(Vec3A::new(2.0, 5.0, 0.0), -Vec3A::Y, 0.0);
+        let rec = ellipsoid.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        let expected = Vec3A::new(rec.point.x / 16.0, rec.point.y, 0.0).normalize();
+        assert!(rec.normal.abs_diff_eq(expected, 1e-4));
+        assert!((rec.t - (5.0 - 0.75f32.sqrt())).abs() < 1e-4);
+
+        // and flipped when hit from the inside
+        let ray = Ray::new(Vec3A::new(2.0, 0.0, 0.0), Vec3A::Y, 0.0);
+        let rec = ellipsoid.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!(!rec.front_face);
+        assert!(rec.normal.abs_diff_eq(-expected, 1e-4));
+    }
+
+    #[test]
+    fn instances_share_prototype() {
+        let glass = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let diamond = Arc::new(Material::Dielectric { refract_index: 2.4 });
+        let sphere: Arc<dyn Hittable> = Sphere::new(Vec3A::ZERO, 1.0, &glass).wrap();
+        let plain =
+            Instance::new(&sphere).with_operation(TransformOperation::Translate(Vec3::X * 3.0));
+        let overridden = Instance::new(&sphere)
+            .with_operation(TransformOperation::Scale(Vec3::splat(2.0)))
+            .with_material(&diamond);
+
+        let ray = Ray::new(Vec3A::new(3.0, 5.0, 0.0), -Vec3A::Y, 0.0);
+        let rec = plain.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-4);
+        assert!(Arc::ptr_eq(&rec.material, &glass));
+
+        let ray = Ray::new(Vec3A::new(0.0, 5.0, 0.0), -Vec3A::Y, 0.0);
+        let rec = overridden.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-4);
+        assert!(Arc::ptr_eq(&rec.material, &diamond));
+
+        // both instances are described with the same prototype
+        let mut names = MaterialNames::default();
+        let prototype = |desc: Option<ObjectDescription>| match desc {
+            Some(ObjectDescription::Instance { prototype, .. }) => prototype,
+            desc => panic!("expected an instance, got {:?}", desc),
+        };
+        assert_eq!(
+            prototype(plain.describe(&mut names)),
+            prototype(overridden.describe(&mut names))
+        );
+
+        // prototypes can't hold instances of their own
+        let nested: Arc<dyn Hittable> = plain.wrap();
+        assert!(Instance::new(&nested).describe(&mut names).is_none());
+    }
 }
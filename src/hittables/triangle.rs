@@ -0,0 +1,152 @@
+//! Triangle implementation
+
+use std::sync::Arc;
+
+use glam::{Vec2, Vec3A};
+
+use crate::{
+    bounds::BoundingBox,
+    material::Material,
+    ray::Ray,
+    scenes::{MaterialNames, ObjectDescription},
+};
+
+use super::{HitRecord, Hittable};
+
+/// Intersects a ray with the triangle between the given points
+///
+/// Returns the distance along the ray, and the barycentric coordinates of the hit for `p1` and `p2`.
+pub(crate) fn intersect_triangle(
+    ray: &Ray,
+    [p0, p1, p2]: [Vec3A; 3],
+    t_min: f32,
+    t_max: f32,
+) -> Option<(f32, f32, f32)> {
+    // Möller-Trumbore intersection,
+    // see https://en.wikipedia.org/wiki/M%C3%B6ller%E2%80%93Trumbore_intersection_algorithm
+    let edge1 = p1 - p0;
+    let edge2 = p2 - p0;
+
+    let p_vec = ray.direction.cross(edge2);
+    let det = edge1.dot(p_vec);
+
+    // ray is parallel to the triangle
+    if det.abs() < 1e-8 {
+        return None;
+    }
+    let inv_det = det.recip();
+
+    // barycentric coords
+    let t_vec = ray.origin - p0;
+    let b1 = t_vec.dot(p_vec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let q_vec = t_vec.cross(edge1);
+    let b2 = ray.direction.dot(q_vec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q_vec) * inv_det;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    Some((t, b1, b2))
+}
+
+/// A triangle defined by three points in space
+///
+/// Optionally holds per-vertex normals and texture coordinates,
+/// which are interpolated across the surface of the triangle.
+#[derive(Debug)]
+pub struct Triangle {
+    p0: Vec3A,
+    p1: Vec3A,
+    p2: Vec3A,
+    /// Per-vertex normals, used for smooth shading
+    normals: Option<[Vec3A; 3]>,
+    /// Per-vertex texture coordinates
+    uvs: Option<[Vec2; 3]>,
+    pub material: Arc<Material>,
+}
+
+impl Triangle {
+    /// Creates a new Triangle.
+    ///
+    /// Without per-vertex data, the triangle is flat shaded
+    /// and uses the barycentric coordinates as its u/v coordinates.
+    pub fn new(p0: Vec3A, p1: Vec3A, p2: Vec3A, m: &Arc<Material>) -> Self {
+        Self {
+            p0,
+            p1,
+            p2,
+            normals: None,
+            uvs: None,
+            material: Arc::clone(m),
+        }
+    }
+
+    /// Adds per-vertex normals to the triangle
+    pub fn with_normals(mut self, normals: [Vec3A; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    /// Adds per-vertex texture coordinates to the triangle
+    pub fn with_uvs(mut self, uvs: [Vec2; 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let (t, b1, b2) = intersect_triangle(ray, [self.p0, self.p1, self.p2], t_min, t_max)?;
+        let b0 = 1.0 - b1 - b2;
+
+        let (u, v) = match self.uvs {
+            Some([uv0, uv1, uv2]) => (b0 * uv0 + b1 * uv1 + b2 * uv2).into(),
+            None => (b1, b2),
+        };
+
+        // the geometric normal decides which side was hit,
+        // while the (possibly interpolated) shading normal is what's recorded
+        let outward_n = (self.p1 - self.p0).cross(self.p2 - self.p0).normalize();
+        let shading_n = match self.normals {
+            Some([n0, n1, n2]) => (b0 * n0 + b1 * n1 + b2 * n2).normalize(),
+            None => outward_n,
+        };
+        let front_face = ray.direction.dot(outward_n) < 0.0;
+        let normal = if front_face { shading_n } else { -shading_n };
+
+        Some(HitRecord {
+            point: ray.at(t),
+            normal,
+            material: Arc::clone(&self.material),
+            t,
+            u,
+            v,
+            front_face,
+        })
+    }
+
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<BoundingBox> {
+        // padded, in case the triangle is axis-aligned
+        let min = self.p0.min(self.p1).min(self.p2) - 0.0001;
+        let max = self.p0.max(self.p1).max(self.p2) + 0.0001;
+        Some(BoundingBox::new(min, max))
+    }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::Triangle {
+            p0: self.p0,
+            p1: self.p1,
+            p2: self.p2,
+            normals: self.normals,
+            uvs: self.uvs,
+            material: materials.name(&self.material)?,
+        })
+    }
+}
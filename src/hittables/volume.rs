@@ -1,10 +1,14 @@
 //! Intersectable volumes/participating media
 
-use std::{f32::INFINITY, ops::Neg, sync::Arc};
+use std::{ops::Neg, sync::Arc};
 
 use rand::Rng;
 
-use crate::{material::Material, textures::Texture};
+use crate::{
+    material::Material,
+    scenes::{is_default_perlin, MaterialNames, ObjectDescription, TextureDescription},
+    textures::Texture,
+};
 
 use super::{HitRecord, Hittable};
 
@@ -31,15 +35,8 @@ impl Hittable for ConstantMedium {
     fn hit(&self, ray: &crate::ray::Ray, t_min: f32, t_max: f32) -> Option<super::HitRecord> {
         let neg_inv_d = self.density.recip().neg();
 
-        let mut min_rec = match self.boundary.hit(ray, -INFINITY, INFINITY) {
-            Some(rec) => rec,
-            None => return None,
-        };
-
-        let mut max_rec = match self.boundary.hit(ray, min_rec.t + 0.0001, INFINITY) {
-            Some(rec) => rec,
-            None => return None,
-        };
+        let mut min_rec = self.boundary.hit(ray, -f32::INFINITY, f32::INFINITY)?;
+        let mut max_rec = self.boundary.hit(ray, min_rec.t + 0.0001, f32::INFINITY)?;
 
         min_rec.t = min_rec.t.max(t_min);
         max_rec.t = max_rec.t.min(t_max);
@@ -75,12 +72,20 @@ impl Hittable for ConstantMedium {
     fn bounding_box(&self, time0: f32, time1: f32) -> Option<crate::bounds::BoundingBox> {
         self.boundary.bounding_box(time0, time1)
     }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        Some(ObjectDescription::ConstantMedium {
+            boundary: Box::new(self.boundary.describe(materials)?),
+            albedo: describe_albedo(&self.material, materials)?,
+            density: self.density,
+        })
+    }
 }
 
 /// A Volume with density as a function of a provided [noise::NoiseFn]
 pub struct NonConstantMedium<N>
 where
-    N: ::noise::NoiseFn<[f64; 3]> + Send + Sync,
+    N: ::noise::NoiseFn<f64, 3> + Send + Sync,
 {
     boundary: Arc<dyn Hittable>,
     material: Arc<Material>,
@@ -90,7 +95,7 @@ where
 
 impl<N> NonConstantMedium<N>
 where
-    N: ::noise::NoiseFn<[f64; 3]> + Send + Sync,
+    N: ::noise::NoiseFn<f64, 3> + Send + Sync,
 {
     pub fn new(
         boundary: &Arc<dyn Hittable>,
@@ -111,18 +116,11 @@ where
 
 impl<N> Hittable for NonConstantMedium<N>
 where
-    N: ::noise::NoiseFn<[f64; 3]> + Send + Sync,
+    N: ::noise::NoiseFn<f64, 3> + Send + Sync + 'static,
 {
     fn hit(&self, ray: &crate::ray::Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        let mut min_rec = match self.boundary.hit(ray, -INFINITY, INFINITY) {
-            Some(rec) => rec,
-            None => return None,
-        };
-
-        let mut max_rec = match self.boundary.hit(ray, min_rec.t + 0.0001, INFINITY) {
-            Some(rec) => rec,
-            None => return None,
-        };
+        let mut min_rec = self.boundary.hit(ray, -f32::INFINITY, f32::INFINITY)?;
+        let mut max_rec = self.boundary.hit(ray, min_rec.t + 0.0001, f32::INFINITY)?;
 
         min_rec.t = min_rec.t.max(t_min);
         max_rec.t = max_rec.t.min(t_max);
@@ -141,7 +139,7 @@ where
         let mut hit_dist = 0.0;
         loop {
             let rand_num = rand::thread_rng().gen::<f32>();
-            hit_dist += -1.0 * (1.0 - rand_num).log10();
+            hit_dist += -(1.0 - rand_num).log10();
             let t = min_rec.t + hit_dist / ray_dir_length;
             let point = ray.at(t);
             let noised = self
@@ -174,4 +172,25 @@ where
     fn bounding_box(&self, time0: f32, time1: f32) -> Option<crate::bounds::BoundingBox> {
         self.boundary.bounding_box(time0, time1)
     }
+
+    fn describe(&self, materials: &mut MaterialNames) -> Option<ObjectDescription> {
+        // only Perlin noise densities can be described
+        if !is_default_perlin(&self.density_fn) {
+            return None;
+        }
+
+        Some(ObjectDescription::NonConstantMedium {
+            boundary: Box::new(self.boundary.describe(materials)?),
+            albedo: describe_albedo(&self.material, materials)?,
+            scale: self.scale,
+        })
+    }
+}
+
+/// Describes the texture of a medium's [Material::Isotropic] material
+fn describe_albedo(material: &Material, names: &MaterialNames) -> Option<TextureDescription> {
+    match material {
+        Material::Isotropic { albedo } => albedo.describe(names),
+        _ => None,
+    }
 }
@@ -0,0 +1,69 @@
+//! Integrators, which gather the light arriving at the camera along each ray
+//!
+//! Besides physically based path tracing, simpler integrators help diagnose scenes quickly.
+
+use std::fmt::Debug;
+
+use rand::rngs::SmallRng;
+
+use crate::{
+    color::Color,
+    hittables::{Hittable, HittableList},
+    ray::Ray,
+};
+
+pub mod debug;
+pub mod direct;
+pub mod occlusion;
+pub mod path;
+pub mod whitted;
+
+pub use debug::*;
+pub use direct::*;
+pub use occlusion::*;
+pub use path::*;
+pub use whitted::*;
+
+/// The scene an [Integrator] gathers light from
+#[derive(Clone, Copy)]
+pub struct RenderContext<'a> {
+    /// Every object in the scene
+    pub world: &'a dyn Hittable,
+    /// The objects sampled as lights, see [Hittable::collect_lights]
+    pub lights: &'a HittableList,
+    /// Color of rays not hitting anything
+    pub bg_color: Color,
+    /// Maximum number of times a ray bounces off surfaces
+    pub bounce_depth: u16,
+}
+
+/// Describes how the color seen along a camera ray is computed
+pub trait Integrator: Send + Sync {
+    /// Returns the color seen along the camera ray `ray`
+    fn radiance(&self, ray: &Ray, context: &RenderContext, rng: &mut SmallRng) -> Color;
+}
+
+impl Debug for dyn Integrator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Integrator")
+    }
+}
+
+/// Possible integrators to choose from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::clap_derive::ArgEnum)]
+pub enum IntegratorType {
+    /// Global illumination by path tracing, see [PathTracer]
+    Path,
+    /// Light reaching surfaces straight from lights only, see [DirectLighting]
+    Direct,
+    /// Ambient occlusion of the surfaces seen, see [AmbientOcclusion]
+    AmbientOcclusion,
+    /// Surface normals of the surfaces seen, see [DebugView]
+    Normals,
+    /// Material colors of the surfaces seen, see [DebugView]
+    Albedo,
+    /// Distance to the surfaces seen, see [DebugView]
+    Depth,
+    /// Mirror reflections, refractions and hard shadows, see [Whitted]
+    Whitted,
+}
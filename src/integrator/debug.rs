@@ -0,0 +1,95 @@
+//! Views of surface properties, for checking scenes
+
+use glam::Vec3A;
+use rand::rngs::SmallRng;
+
+use crate::{color::Color, ray::Ray};
+
+use super::{Integrator, RenderContext};
+
+/// Surface property shown by a [DebugView]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugPass {
+    /// The normal facing the ray, mapped from -1..1 to 0..1 on each axis
+    Normal,
+    /// The base color of the material, see [crate::Material::albedo]
+    Albedo,
+    /// The distance along the ray, from white up close to black at `max_depth`
+    Depth,
+}
+
+/// Shows a property of the first surface each ray hits, ignoring lighting
+#[derive(Debug, Clone, Copy)]
+pub struct DebugView {
+    /// Surface property shown
+    pub pass: DebugPass,
+    /// Distance shown as black by [DebugPass::Depth]
+    pub max_depth: f32,
+}
+
+impl DebugView {
+    /// Creates a new DebugView
+    pub fn new(pass: DebugPass, max_depth: f32) -> Self {
+        Self { pass, max_depth }
+    }
+}
+
+impl Integrator for DebugView {
+    fn radiance(&self, ray: &Ray, context: &RenderContext, _rng: &mut SmallRng) -> Color {
+        let rec = match (context.world.hit(ray, 0.001, f32::INFINITY), self.pass) {
+            (Some(rec), _) => rec,
+            (None, DebugPass::Albedo) => return context.bg_color,
+            (None, _) => return Color::new(Vec3A::ZERO),
+        };
+
+        let value = match self.pass {
+            DebugPass::Normal => (rec.normal + 1.0) / 2.0,
+            DebugPass::Albedo => rec.material.albedo(rec.u, rec.v, rec.point).into(),
+            DebugPass::Depth => {
+                let distance = rec.t * ray.direction.length();
+                Vec3A::splat(1.0 - (distance / self.max_depth).clamp(0.0, 1.0))
+            }
+        };
+        Color::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{
+        hittables::{Hittable, HittableList, Sphere},
+        material::Material,
+    };
+
+    #[test]
+    fn shows_sphere_hit() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let world: HittableList = vec![Sphere::new(Vec3A::ZERO, 1.0, &mat).wrap()];
+        let lights = HittableList::new();
+        let context = RenderContext {
+            world: &world,
+            lights: &lights,
+            bg_color: Color::new(Vec3A::ONE),
+            bounce_depth: 1,
+        };
+        // an unnormalized direction, hitting the sphere head on at a distance of 4
+        let ray = Ray::new(Vec3A::new(0.0, 0.0, 5.0), Vec3A::new(0.0, 0.0, -2.0), 0.0);
+
+        let depth = DebugView::new(DebugPass::Depth, 10.0).radiance(&ray, &context, &mut rng);
+        assert!(Vec3A::from(depth).abs_diff_eq(Vec3A::splat(0.6), 1e-4));
+
+        let normal = DebugView::new(DebugPass::Normal, 10.0).radiance(&ray, &context, &mut rng);
+        assert!(Vec3A::from(normal).abs_diff_eq(Vec3A::new(0.5, 0.5, 1.0), 1e-4));
+
+        // misses show black, rather than the background
+        let ray = Ray::new(Vec3A::new(0.0, 5.0, 5.0), -Vec3A::Z, 0.0);
+        let miss = DebugView::new(DebugPass::Depth, 10.0).radiance(&ray, &context, &mut rng);
+        assert_eq!(Vec3A::from(miss), Vec3A::ZERO);
+    }
+}
@@ -0,0 +1,133 @@
+//! Lighting from the first bounce only
+
+use glam::Vec3A;
+use rand::rngs::SmallRng;
+
+use crate::{color::Color, hittables::Hittable, ray::Ray, scatter::power_heuristic};
+
+use super::{Integrator, RenderContext};
+
+/// Gathers the light reaching the surfaces seen straight from lights, ignoring indirect light
+///
+/// Combines sampling the lights with scattering off the surface, by multiple importance sampling,
+/// so lights show up in reflections off metal and glass too.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DirectLighting;
+
+impl Integrator for DirectLighting {
+    fn radiance(&self, ray: &Ray, context: &RenderContext, rng: &mut SmallRng) -> Color {
+        let rec = match context.world.hit(ray, 0.001, f32::INFINITY) {
+            Some(rec) => rec,
+            None => return context.bg_color,
+        };
+        let mat = &rec.material;
+
+        let mut color = match mat.emit(rec.u, rec.v, rec.point) {
+            Some(emitted) => Vec3A::from(emitted),
+            None => Vec3A::ZERO,
+        };
+
+        // sample the lights
+        if let Some(sample) = ray.sample_light(context.world, context.lights, &rec, rng) {
+            color += sample.radiance * power_heuristic(sample.light_pdf, sample.scatter_pdf);
+        }
+
+        // scatter into whatever light is there, without going any further
+        if let Some((scattered, attenuation)) = mat.scatter(ray, &rec, rng) {
+            let incoming = match context.world.hit(&scattered, 0.001, f32::INFINITY) {
+                Some(light_rec) => light_rec
+                    .material
+                    .emit(light_rec.u, light_rec.v, light_rec.point)
+                    .map(|emitted| {
+                        let weight = match mat.evaluate(&rec, scattered.direction) {
+                            Some((_, pdf)) => power_heuristic(
+                                pdf,
+                                context
+                                    .lights
+                                    .pdf_value(scattered.origin, scattered.direction),
+                            ),
+                            None => 1.0,
+                        };
+                        weight * Vec3A::from(emitted)
+                    }),
+                None => Some(Vec3A::from(context.bg_color)),
+            };
+            color += attenuation * incoming.unwrap_or(Vec3A::ZERO);
+        }
+
+        Color::new(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{
+        hittables::{HittableList, Quad},
+        integrator::PathTracer,
+        material::Material,
+    };
+
+    #[test]
+    fn matches_single_bounce_path() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let grey = Arc::new(Material::Lambertian {
+            albedo: Arc::new(Color::new(Vec3A::splat(0.5))),
+        });
+        let light = Arc::new(Material::DiffuseLight {
+            albedo: Arc::new(Color::new(Vec3A::ONE)),
+            brightness: 4.0,
+        });
+        // a diffuse floor, lit by a quad light above it
+        let world: HittableList = vec![
+            Quad::new(
+                Vec3A::new(-10.0, 0.0, -10.0),
+                Vec3A::new(-10.0, 0.0, 10.0),
+                Vec3A::new(10.0, 0.0, 10.0),
+                Vec3A::new(10.0, 0.0, -10.0),
+                &grey,
+            )
+            .wrap(),
+            Quad::new(
+                Vec3A::new(-0.5, 2.0, -0.5),
+                Vec3A::new(-0.5, 2.0, 0.5),
+                Vec3A::new(0.5, 2.0, 0.5),
+                Vec3A::new(0.5, 2.0, -0.5),
+                &light,
+            )
+            .wrap(),
+        ];
+        let mut lights = HittableList::new();
+        world.collect_lights(&mut lights);
+        // the camera ray's hit counts towards the bounce depth,
+        // so paths end on their second hit, only gathering light bounced once off the floor
+        let context = RenderContext {
+            world: &world,
+            lights: &lights,
+            bg_color: Color::new(Vec3A::ZERO),
+            bounce_depth: 2,
+        };
+        let ray = Ray::new(Vec3A::new(0.0, 1.0, 3.0), Vec3A::new(0.3, -1.0, -3.0), 0.0);
+
+        let samples = 20_000;
+        let mut mean = |integrator: &dyn Integrator| {
+            (0..samples)
+                .map(|_| Vec3A::from(integrator.radiance(&ray, &context, &mut rng)))
+                .fold(Vec3A::ZERO, |acc, c| acc + c)
+                / samples as f32
+        };
+        let direct = mean(&DirectLighting);
+        let path = mean(&PathTracer::default());
+        assert!(direct.x > 0.0);
+        assert!(
+            (direct.x - path.x).abs() < 0.01 * path.x,
+            "{} != {}",
+            direct,
+            path
+        );
+    }
+}
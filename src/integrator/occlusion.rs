@@ -0,0 +1,91 @@
+//! Ambient occlusion
+
+use glam::Vec3A;
+use rand::rngs::SmallRng;
+
+use crate::{color::Color, ray::Ray, utils::random::rand_vec3_in_unit_sphere};
+
+use super::{Integrator, RenderContext};
+
+/// Shades surfaces by how much of the space around them is open, ignoring lights and materials
+///
+/// Surfaces are white where nothing is within `distance` of them, and darken in corners and crevices.
+/// Directions are sampled by the cosine distribution, like diffuse materials scatter.
+#[derive(Debug, Clone, Copy)]
+pub struct AmbientOcclusion {
+    /// Number of directions sampled for each ray
+    pub samples: u32,
+    /// Distance within which objects occlude a surface
+    pub distance: f32,
+}
+
+impl AmbientOcclusion {
+    /// Creates a new AmbientOcclusion
+    pub fn new(samples: u32, distance: f32) -> Self {
+        Self { samples, distance }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, ray: &Ray, context: &RenderContext, rng: &mut SmallRng) -> Color {
+        let rec = match context.world.hit(ray, 0.001, f32::INFINITY) {
+            Some(rec) => rec,
+            None => return Color::new(Vec3A::ONE),
+        };
+
+        let open = (0..self.samples)
+            .filter(|_| {
+                let direction = (rec.normal + rand_vec3_in_unit_sphere(rng)).normalize_or_zero();
+                let occlusion_ray = Ray::new(rec.point, direction, ray.time);
+                context
+                    .world
+                    .hit(&occlusion_ray, 0.001, self.distance)
+                    .is_none()
+            })
+            .count();
+
+        Color::new(Vec3A::splat(open as f32 / self.samples as f32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{
+        hittables::{Hittable, HittableList, Sphere},
+        material::Material,
+    };
+
+    #[test]
+    fn open_and_enclosed() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mat = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let lights = HittableList::new();
+        let integrator = AmbientOcclusion::new(64, 100.0);
+        let ray = Ray::new(Vec3A::new(0.0, 0.0, 5.0), -Vec3A::Z, 0.0);
+
+        // nothing occludes the outside of a lone sphere
+        let world: HittableList = vec![Sphere::new(Vec3A::ZERO, 1.0, &mat).wrap()];
+        let context = RenderContext {
+            world: &world,
+            lights: &lights,
+            bg_color: Color::new(Vec3A::ZERO),
+            bounce_depth: 1,
+        };
+        let open = integrator.radiance(&ray, &context, &mut rng);
+        assert_eq!(Vec3A::from(open), Vec3A::ONE);
+
+        // while the inside of a sphere is occluded everywhere
+        let world: HittableList = vec![Sphere::new(Vec3A::ZERO, 10.0, &mat).wrap()];
+        let context = RenderContext {
+            world: &world,
+            ..context
+        };
+        let enclosed = integrator.radiance(&ray, &context, &mut rng);
+        assert_eq!(Vec3A::from(enclosed), Vec3A::ZERO);
+    }
+}
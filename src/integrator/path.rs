@@ -0,0 +1,42 @@
+//! Physically based global illumination
+
+use rand::rngs::SmallRng;
+
+use crate::{color::Color, ray::Ray};
+
+use super::{Integrator, RenderContext};
+
+/// An unbiased path tracer, following rays through every bounce
+///
+/// See [Ray::trace_path] for details.
+#[derive(Debug, Clone, Copy)]
+pub struct PathTracer {
+    /// Number of bounces after which paths may be terminated by Russian roulette
+    pub roulette_depth: u16,
+}
+
+impl PathTracer {
+    /// Creates a new PathTracer
+    pub fn new(roulette_depth: u16) -> Self {
+        Self { roulette_depth }
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self::new(5)
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, ray: &Ray, context: &RenderContext, rng: &mut SmallRng) -> Color {
+        ray.trace_path(
+            context.world,
+            context.lights,
+            context.bounce_depth,
+            self.roulette_depth,
+            context.bg_color,
+            rng,
+        )
+    }
+}
@@ -0,0 +1,56 @@
+//! Whitted-style ray tracing
+
+use glam::Vec3A;
+use rand::rngs::SmallRng;
+
+use crate::{color::Color, ray::Ray};
+
+use super::{Integrator, RenderContext};
+
+/// Follows rays through mirror reflections and refractions,
+/// lighting the first other surface they reach by sampling the lights only
+///
+/// Points are sampled across the area of each light, giving soft shadows over several samples per pixel,
+/// but there is no indirect light between surfaces.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Whitted;
+
+impl Integrator for Whitted {
+    fn radiance(&self, ray: &Ray, context: &RenderContext, rng: &mut SmallRng) -> Color {
+        let mut color = Vec3A::ZERO;
+        let mut throughput = Vec3A::ONE;
+        let mut ray = *ray;
+
+        for _ in 0..context.bounce_depth {
+            let rec = match context.world.hit(&ray, 0.001, f32::INFINITY) {
+                Some(rec) => rec,
+                None => {
+                    color += throughput * Vec3A::from(context.bg_color);
+                    break;
+                }
+            };
+            let mat = &rec.material;
+
+            if let Some(emitted) = mat.emit(rec.u, rec.v, rec.point) {
+                color += throughput * Vec3A::from(emitted);
+            }
+            if let Some(sample) = ray.sample_light(context.world, context.lights, &rec, rng) {
+                color += throughput * sample.radiance;
+            }
+
+            // only metal and glass pass light on
+            if !mat.is_specular() {
+                break;
+            }
+            match mat.scatter(&ray, &rec, rng) {
+                Some((scattered, attenuation)) => {
+                    throughput *= attenuation;
+                    ray = scattered;
+                }
+                None => break,
+            }
+        }
+
+        Color::new(color)
+    }
+}
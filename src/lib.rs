@@ -0,0 +1,53 @@
+//! Toy Ray-Tracing Renderer
+//!
+//! Based on [Peter Shirley's Ray Tracing in One Weekend](https://raytracing.github.io/) book series.
+//!
+//! A scene consists of a [Camera] and some [Hittable] objects,
+//! which a [Renderer] turns into an image held in memory:
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use glam::Vec3A;
+//! use lustre::{
+//!     hittables::Sphere, scenes::CameraDescription, Color, Hittable, HittableList, Material,
+//!     Renderer,
+//! };
+//!
+//! let red = Arc::new(Material::Lambertian {
+//!     albedo: Arc::new(Color::new(Vec3A::new(0.8, 0.1, 0.1))),
+//! });
+//! let world: HittableList = vec![Sphere::new(Vec3A::ZERO, 1.0, &red).wrap()];
+//!
+//! let cam = CameraDescription::default();
+//! let dimensions = cam.dimensions(32);
+//! let renderer = Renderer::new(dimensions.x, dimensions.y, 4, 8);
+//! let image = renderer.render_scene((cam.build(), world));
+//! assert_eq!(image.width(), 32);
+//! ```
+//!
+//! Custom objects and textures can take part in a scene
+//! by implementing the [Hittable] and [textures::Texture] traits.
+
+pub mod bounds;
+pub mod bvh;
+pub mod camera;
+pub mod color;
+pub mod hittables;
+pub mod integrator;
+pub mod material;
+pub mod ray;
+pub mod render;
+pub mod scatter;
+pub mod scenes;
+pub mod textures;
+pub mod utils;
+
+pub use bvh::{BvhNode, LinearBvh};
+pub use camera::Camera;
+pub use color::Color;
+pub use hittables::{HitRecord, Hittable, HittableList};
+pub use integrator::Integrator;
+pub use material::Material;
+pub use ray::Ray;
+pub use render::Renderer;
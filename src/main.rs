@@ -1,25 +1,22 @@
-use rand::SeedableRng;
-use render::Renderer;
-use scenes::get_scene;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::{
-    bvh::BvhNode,
-    cli::{Arguments, Parser},
+use lustre::{
+    integrator::*,
+    render::{
+        sample_heatmap, save_linear, AdaptiveSampling, Denoiser, DisplayTransform, RenderOutput,
+        SnapshotSchedule,
+    },
+    scenes::{describe_scene, get_scene, load_scene_file, save_scene_file},
+    Hittable, LinearBvh, Renderer,
 };
+use rand::SeedableRng;
+
+use crate::cli::{Arguments, Parser};
 
-mod bounds;
-mod bvh;
-mod camera;
 mod cli;
-mod color;
-mod hittables;
-mod material;
-mod ray;
-mod render;
-mod scatter;
-mod scenes;
-mod textures;
-mod utils;
 
 fn main() {
     // Parsing cli args
@@ -42,18 +39,146 @@ fn main() {
     };
 
     // Get scene
-    let (cam, world, dimensions) = get_scene(img_w, scene, &mut rng);
-    let world = BvhNode::new(world, 0.0, 1.0, &mut rng);
+    let (cam, world, dimensions) = match cli_args.scene_file {
+        Some(scene_file) => match load_scene_file(&scene_file, img_w) {
+            Ok(scene) => scene,
+            Err(why) => {
+                eprintln!("Failed to load scene file {:?}: {}", scene_file, why);
+                std::process::exit(1);
+            }
+        },
+        None => get_scene(img_w, scene, &mut rng),
+    };
+
+    // Write out the scene instead of rendering it
+    if let Some(dump_file) = cli_args.dump_scene {
+        let base_dir = dump_file.parent().unwrap_or_else(|| Path::new(""));
+        match describe_scene(&cam, &world, base_dir)
+            .and_then(|desc| save_scene_file(&dump_file, &desc))
+        {
+            Ok(()) => println!("Scene written to {:?}", dump_file),
+            Err(why) => {
+                eprintln!("Failed to write scene file {:?}: {}", dump_file, why);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let world = match LinearBvh::new(world, 0.0, 1.0) {
+        Ok(bvh) => bvh,
+        Err(why) => {
+            eprintln!("Failed to build scene: {}", why);
+            std::process::exit(1);
+        }
+    };
 
-    let renderer = Renderer::new(dimensions.x, dimensions.y, samples_per_pixel, bounce_depth);
+    // size of the scene and distance to its far end, scaling the distances used by some integrators
+    let look_from = cam.description().look_from;
+    let (extent, max_depth) = match world.bounding_box(0.0, 1.0) {
+        Some(bbox) => (
+            (bbox.max - bbox.min).length(),
+            bbox.corners()
+                .iter()
+                .map(|corner| corner.distance(look_from))
+                .fold(0.0, f32::max),
+        ),
+        None => {
+            let distance = 2.0 * look_from.distance(cam.description().look_at);
+            (distance, distance)
+        }
+    };
+    let integrator: Arc<dyn Integrator> = match cli_args.integrator {
+        IntegratorType::Path => Arc::new(PathTracer::new(cli_args.roulette_depth)),
+        IntegratorType::Direct => Arc::new(DirectLighting),
+        IntegratorType::AmbientOcclusion => Arc::new(AmbientOcclusion::new(
+            cli_args.ao_samples,
+            cli_args.ao_distance.unwrap_or(0.1 * extent),
+        )),
+        IntegratorType::Normals => Arc::new(DebugView::new(DebugPass::Normal, max_depth)),
+        IntegratorType::Albedo => Arc::new(DebugView::new(DebugPass::Albedo, max_depth)),
+        IntegratorType::Depth => Arc::new(DebugView::new(DebugPass::Depth, max_depth)),
+        IntegratorType::Whitted => Arc::new(Whitted),
+    };
 
-    let img_buf = renderer.render_scene((cam, world));
+    let mut renderer = Renderer::new(dimensions.x, dimensions.y, samples_per_pixel, bounce_depth)
+        .with_integrator(&integrator);
+    if let Some(noise_threshold) = cli_args.adaptive {
+        renderer = renderer.with_adaptive_sampling(
+            AdaptiveSampling::new(noise_threshold).with_batch_size(cli_args.adaptive_batch),
+        );
+    }
 
-    // write image to file
-    match img_buf.save(output_file.clone()) {
+    // post-processing, from the linear radiance of a render to the image written out
+    let mut display = DisplayTransform::new(cli_args.tone_mapper, cli_args.exposure);
+    if let Some(white_point) = cli_args.white_point {
+        display = display.with_white_point(white_point);
+    }
+    let denoiser = cli_args
+        .denoise
+        .then(|| Denoiser::new(cli_args.denoise_iterations));
+    let finish_image = |output: &RenderOutput| match (&denoiser, &output.aovs) {
+        (Some(denoiser), Some(aovs)) => denoiser.apply(&output.image, aovs),
+        _ => output.image.clone(),
+    };
+
+    // the denoiser is guided by the auxiliary passes
+    let capture_aovs = !cli_args.aovs.is_empty() || cli_args.denoise;
+    let schedule = SnapshotSchedule {
+        passes: cli_args.snapshot_passes,
+        interval: cli_args.snapshot_interval,
+    };
+    let output = if schedule == SnapshotSchedule::default() {
+        renderer.render((cam, world), capture_aovs)
+    } else {
+        // keep the current estimate on disk, overwritten as it improves
+        renderer.render_progressive((cam, world), capture_aovs, schedule, |snapshot| {
+            if let Err(why) = save_linear(&finish_image(snapshot), &output_file, &display) {
+                eprintln!("Failed to write snapshot: {}", why);
+            }
+        })
+    };
+
+    // write image to file, unclamped if its format allows
+    match save_linear(&finish_image(&output), &output_file, &display) {
         Ok(()) => println!("Image written to {:?}", output_file),
         Err(why) => {
             eprintln!("Failed to write: {}", why);
         }
     }
+
+    // write auxiliary passes next to it
+    if let Some(aovs) = output.aovs {
+        for aov in cli_args.aovs {
+            let aov_file = pass_path(&output_file, aov.name());
+            match aovs.save(aov, &aov_file) {
+                Ok(()) => println!("{} pass written to {:?}", aov.name(), aov_file),
+                Err(why) => {
+                    eprintln!("Failed to write {} pass: {}", aov.name(), why);
+                }
+            }
+        }
+    }
+
+    if cli_args.sample_heatmap {
+        // always 8-bit, as a picture rather than data
+        let heatmap_file = pass_path(&output_file, "samples").with_extension("png");
+        match sample_heatmap(&output.sample_counts, samples_per_pixel).save(&heatmap_file) {
+            Ok(()) => println!("Sample heatmap written to {:?}", heatmap_file),
+            Err(why) => {
+                eprintln!("Failed to write sample heatmap: {}", why);
+            }
+        }
+    }
+}
+
+/// Returns the path of an auxiliary output, inserting its name before the output's extension
+fn pass_path(output_file: &Path, name: &str) -> PathBuf {
+    let mut file_name = output_file.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(name);
+    if let Some(extension) = output_file.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+    output_file.with_file_name(file_name)
 }
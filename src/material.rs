@@ -1,6 +1,6 @@
 //! Implementation of material types
 
-use std::{f32::EPSILON, sync::Arc};
+use std::{f32::consts::PI, sync::Arc};
 
 use glam::Vec3A;
 use rand::Rng;
@@ -10,6 +10,7 @@ use crate::{
     hittables::HitRecord,
     ray::Ray,
     scatter::{reflect, refract},
+    scenes::{MaterialDescription, MaterialNames},
     textures::Texture,
 };
 
@@ -62,7 +63,7 @@ impl Material {
                 let mut scatter_dir = rec.normal + rand_unit_v;
 
                 // If the scatter direction is close to zero in all dimensions
-                if scatter_dir.cmplt(Vec3A::splat(EPSILON)).all() {
+                if scatter_dir.cmplt(Vec3A::splat(f32::EPSILON)).all() {
                     scatter_dir = rec.normal;
                 }
 
@@ -118,6 +119,49 @@ impl Material {
         }
     }
 
+    /// Evaluates how much light arriving along `direction` the material scatters back along the ray that hit it
+    ///
+    /// Returns the attenuation, including the cosine term, and the probability density,
+    /// over solid angle, of [Material::scatter] picking `direction`.
+    /// Returns `None` for materials scattering into too few directions to sample lights through,
+    /// like metal and glass, or not scattering at all.
+    pub fn evaluate(&self, rec: &HitRecord, direction: Vec3A) -> Option<(Vec3A, f32)> {
+        let (albedo, pdf) = match self {
+            Material::Lambertian { albedo } => {
+                // scattering follows the cosine distribution, so the pdf carries the cosine term
+                let cosine = rec.normal.dot(direction.normalize()).max(0.0);
+                (albedo, cosine / PI)
+            }
+            Material::Isotropic { albedo } => (albedo, 0.25 / PI),
+            _ => return None,
+        };
+        let attenuation = Vec3A::from(albedo.color(rec.u, rec.v, rec.point)) * pdf;
+        Some((attenuation, pdf))
+    }
+
+    /// Returns the base color of the material, as used for [crate::integrator::DebugView]s
+    ///
+    /// Glass is white.
+    pub fn albedo(&self, u: f32, v: f32, point: Vec3A) -> Color {
+        match self {
+            Material::Lambertian { albedo }
+            | Material::Metal { albedo, .. }
+            | Material::DiffuseLight { albedo, .. }
+            | Material::Isotropic { albedo } => albedo.color(u, v, point),
+            Material::Dielectric { .. } => Color::new(Vec3A::ONE),
+        }
+    }
+
+    /// Returns whether the material scatters light into a single direction, like metal and glass
+    pub fn is_specular(&self) -> bool {
+        matches!(self, Material::Metal { .. } | Material::Dielectric { .. })
+    }
+
+    /// Returns whether the material emits light
+    pub fn is_emissive(&self) -> bool {
+        matches!(self, Material::DiffuseLight { .. })
+    }
+
     /// Returns the emmited color of light from the material, if any.
     pub fn emit(&self, u: f32, v: f32, point: Vec3A) -> Option<Color> {
         match self {
@@ -130,4 +174,32 @@ impl Material {
             _ => None,
         }
     }
+
+    /// Returns a description of the material, as used by scene files
+    ///
+    /// File paths are written through `names`.
+    /// Returns `None` if any of the material's textures can't be described.
+    pub fn describe(&self, names: &MaterialNames) -> Option<MaterialDescription> {
+        let desc = match self {
+            Material::Lambertian { albedo } => MaterialDescription::Lambertian {
+                albedo: albedo.describe(names)?,
+            },
+            Material::Metal { albedo, roughness } => MaterialDescription::Metal {
+                albedo: albedo.describe(names)?,
+                roughness: *roughness,
+            },
+            Material::Dielectric { refract_index } => MaterialDescription::Dielectric {
+                refract_index: *refract_index,
+            },
+            Material::DiffuseLight { albedo, brightness } => MaterialDescription::DiffuseLight {
+                albedo: albedo.describe(names)?,
+                brightness: *brightness,
+            },
+            Material::Isotropic { albedo } => MaterialDescription::Isotropic {
+                albedo: albedo.describe(names)?,
+            },
+        };
+
+        Some(desc)
+    }
 }
@@ -1,11 +1,13 @@
 //! Implementation of a 3-dimensional Ray.
 
-use std::f32::INFINITY;
-
 use glam::Vec3A;
 use rand::Rng;
 
-use crate::{color::Color, hittables::Hittable};
+use crate::{
+    color::Color,
+    hittables::{HitRecord, Hittable, HittableList},
+    scatter::power_heuristic,
+};
 
 /// A 3-dimensional Ray
 ///
@@ -38,11 +40,32 @@ impl Ray {
     /// Returns a [`Color`] value based on the accumulated light and color at the initial intersection point.
     ///
     /// Uses `bounce_depth` to limit the amount of recursion when gathering contributions.
+    /// Light reaching diffuse surfaces is gathered both by sampling the `lights` directly
+    /// and by scattering into them, combined by multiple importance sampling.
+    ///
+    /// See [Ray::trace_path] for an iterative version, which doesn't grow the stack with each bounce.
     pub fn shade(
         &self,
-        hittable: &impl Hittable,
+        hittable: &(impl Hittable + ?Sized),
+        lights: &HittableList,
+        bounce_depth: u16,
+        bg_color: Color,
+        rng: &mut impl Rng,
+    ) -> Color {
+        self.shade_scattered(hittable, lights, bounce_depth, bg_color, None, rng)
+    }
+
+    /// Like [Ray::shade], for a ray scattered with a probability density of `scatter_pdf`
+    ///
+    /// `scatter_pdf` is `None` for rays that light sampling couldn't have picked instead,
+    /// like camera rays and those reflected by metals.
+    fn shade_scattered(
+        &self,
+        hittable: &(impl Hittable + ?Sized),
+        lights: &HittableList,
         bounce_depth: u16,
         bg_color: Color,
+        scatter_pdf: Option<f32>,
         rng: &mut impl Rng,
     ) -> Color {
         // Limit recursion depth
@@ -51,26 +74,41 @@ impl Ray {
         }
 
         // Check for a hit against the `hittable` parameter
-        match hittable.hit(self, 0.001, INFINITY) {
+        match hittable.hit(self, 0.001, f32::INFINITY) {
             // successful hit, let's do some light gathering
             Some(rec) => {
                 // need a ref since scatter takes a ref to rec later
                 let mat = &rec.material;
-                // gather any emitted light contribution
+                // gather any emitted light contribution,
+                // weighed against light sampling having found it too
                 let emit_contrib = match mat.emit(rec.u, rec.v, rec.point) {
-                    Some(color) => Vec3A::from(color),
+                    Some(color) => {
+                        let weight = match scatter_pdf {
+                            Some(pdf) => {
+                                power_heuristic(pdf, lights.pdf_value(self.origin, self.direction))
+                            }
+                            None => 1.0,
+                        };
+                        weight * Vec3A::from(color)
+                    }
                     None => Vec3A::ZERO,
                 };
 
+                // gather light arriving straight from a light
+                let direct_contrib = self.sample_lights(hittable, lights, &rec, rng);
+
                 // gather any scattered light contribution
                 let scatter_contrib = match mat.scatter(self, &rec, rng) {
                     // A successful ray scatter leads to more contributions.
                     Some((scattered, attenuation)) => {
+                        let pdf = mat.evaluate(&rec, scattered.direction).map(|(_, pdf)| pdf);
                         attenuation
-                            * Vec3A::from(scattered.shade(
+                            * Vec3A::from(scattered.shade_scattered(
                                 hittable,
+                                lights,
                                 bounce_depth - 1,
                                 bg_color,
+                                pdf,
                                 rng,
                             ))
                     }
@@ -78,12 +116,171 @@ impl Ray {
                     None => Vec3A::ZERO,
                 };
 
-                // both emissives and scattered light contribute, unless they're zeroed
-                // with current materials, one of these will always be zero
-                Color::new(emit_contrib + scatter_contrib)
+                // emissives, direct and scattered light all contribute, unless they're zeroed
+                Color::new(emit_contrib + direct_contrib + scatter_contrib)
             }
             // without a hit, functions like a miss shader
             None => bg_color,
         }
     }
+
+    /// Like [Ray::shade], but follows the path in a loop rather than recursing
+    ///
+    /// After `roulette_depth` bounces, paths are randomly terminated with a probability
+    /// growing as their throughput drops, and surviving paths are weighed up to make up for it.
+    /// This keeps the same expected color, while spending less work on paths that contribute little.
+    pub fn trace_path(
+        &self,
+        hittable: &(impl Hittable + ?Sized),
+        lights: &HittableList,
+        bounce_depth: u16,
+        roulette_depth: u16,
+        bg_color: Color,
+        rng: &mut impl Rng,
+    ) -> Color {
+        let mut color = Vec3A::ZERO;
+        // fraction of the light arriving along the current ray that makes it to the camera
+        let mut throughput = Vec3A::ONE;
+        let mut ray = *self;
+        let mut scatter_pdf = None;
+
+        for depth in 0..bounce_depth {
+            let rec = match hittable.hit(&ray, 0.001, f32::INFINITY) {
+                Some(rec) => rec,
+                // without a hit, functions like a miss shader
+                None => {
+                    color += throughput * Vec3A::from(bg_color);
+                    break;
+                }
+            };
+            let mat = &rec.material;
+
+            // gather any emitted light contribution,
+            // weighed against light sampling having found it too
+            if let Some(emitted) = mat.emit(rec.u, rec.v, rec.point) {
+                let weight = match scatter_pdf {
+                    Some(pdf) => power_heuristic(pdf, lights.pdf_value(ray.origin, ray.direction)),
+                    None => 1.0,
+                };
+                color += throughput * weight * Vec3A::from(emitted);
+            }
+
+            // gather light arriving straight from a light
+            color += throughput * ray.sample_lights(hittable, lights, &rec, rng);
+
+            // continue along the scattered ray, if any
+            let Some((scattered, attenuation)) = mat.scatter(&ray, &rec, rng) else {
+                break;
+            };
+            scatter_pdf = mat.evaluate(&rec, scattered.direction).map(|(_, pdf)| pdf);
+            throughput *= attenuation;
+            ray = scattered;
+
+            if depth + 1 >= roulette_depth {
+                let survival = throughput.max_element().min(1.0);
+                if rng.gen::<f32>() >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+        }
+
+        Color::new(color)
+    }
+
+    /// Returns the light reaching `rec` from a point sampled on the `lights`, scattered back along the ray
+    ///
+    /// The light is weighed against the material scattering towards the same point, by multiple importance sampling.
+    fn sample_lights(
+        &self,
+        hittable: &(impl Hittable + ?Sized),
+        lights: &HittableList,
+        rec: &HitRecord,
+        rng: &mut impl Rng,
+    ) -> Vec3A {
+        match self.sample_light(hittable, lights, rec, rng) {
+            Some(sample) => sample.radiance * power_heuristic(sample.light_pdf, sample.scatter_pdf),
+            None => Vec3A::ZERO,
+        }
+    }
+
+    /// Samples a point on the `lights`, returning the light reaching `rec` from it, scattered back along the ray
+    ///
+    /// Returns `None` if the point is hidden, or the material can't be evaluated in a given direction.
+    pub fn sample_light(
+        &self,
+        hittable: &(impl Hittable + ?Sized),
+        lights: &HittableList,
+        rec: &HitRecord,
+        rng: &mut impl Rng,
+    ) -> Option<LightSample> {
+        let direction = lights.sample_direction(rec.point, rng)?;
+        let (attenuation, scatter_pdf) = rec.material.evaluate(rec, direction)?;
+        let light_pdf = lights.pdf_value(rec.point, direction);
+        if scatter_pdf <= 0.0 || light_pdf <= 0.0 {
+            return None;
+        }
+
+        // shadow ray, only lit if nothing else is in the way
+        let shadow_ray = Ray::new(rec.point, direction, self.time);
+        let light_rec = hittable.hit(&shadow_ray, 0.001, f32::INFINITY)?;
+        let emitted = light_rec
+            .material
+            .emit(light_rec.u, light_rec.v, light_rec.point)?;
+
+        Some(LightSample {
+            radiance: attenuation * Vec3A::from(emitted) / light_pdf,
+            light_pdf,
+            scatter_pdf,
+        })
+    }
+}
+
+/// Light arriving at a surface from a point sampled on a light, see [Ray::sample_light]
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    /// The light scattered back along the ray, divided by `light_pdf`
+    pub radiance: Vec3A,
+    /// Probability density of sampling the point's direction among the lights
+    pub light_pdf: f32,
+    /// Probability density of the material scattering towards the point
+    pub scatter_pdf: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{
+        bvh::LinearBvh,
+        scenes::{get_scene, SceneType},
+    };
+
+    #[test]
+    fn iterative_matches_recursive() {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+        let (cam, world, _) = get_scene(100, SceneType::CornellBox, &mut rng);
+        let world = LinearBvh::new(world, 0.0, 1.0).unwrap();
+        let mut lights = HittableList::new();
+        world.collect_lights(&mut lights);
+
+        // average over random pixels, with roulette from the first bounce on
+        const SAMPLES: usize = 20_000;
+        let (mut recursive, mut iterative) = (Vec3A::ZERO, Vec3A::ZERO);
+        for _ in 0..SAMPLES {
+            let ray = cam.get_ray(rng.gen(), rng.gen(), &mut rng);
+            recursive += Vec3A::from(ray.shade(&world, &lights, 50, cam.bg_color, &mut rng));
+            iterative +=
+                Vec3A::from(ray.trace_path(&world, &lights, 50, 0, cam.bg_color, &mut rng));
+        }
+
+        let (recursive, iterative) = (recursive / SAMPLES as f32, iterative / SAMPLES as f32);
+        assert!(
+            iterative.abs_diff_eq(recursive, 0.05 * recursive.max_element()),
+            "{} != {}",
+            iterative,
+            recursive
+        );
+    }
 }
@@ -1,23 +1,58 @@
 //! Render an image given a [Camera] and a [Hittable].
 
+use std::{sync::Arc, time::Instant};
+
 use glam::Vec3A;
-use indicatif::ParallelProgressIterator;
+use image::{ImageBuffer, Luma, Rgb, Rgb32FImage, RgbImage};
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 use rayon::prelude::*;
 
-use crate::{camera::Camera, color::Color, hittables::Hittable, utils::progress::get_progressbar};
+use crate::{
+    camera::Camera,
+    hittables::{Hittable, HittableList},
+    integrator::{Integrator, PathTracer, RenderContext},
+    utils::progress::get_progressbar,
+};
+
+pub mod adaptive;
+pub mod aov;
+pub mod denoise;
+pub mod output;
+pub mod progressive;
+pub mod tonemap;
+pub use adaptive::*;
+pub use aov::*;
+pub use denoise::*;
+pub use output::*;
+pub use progressive::*;
+pub use tonemap::*;
 
 /// Image Renderer storing scene context values such as image dimensions and samples per pixel
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Renderer {
     image_width: u32,
     image_height: u32,
     samples_per_pixel: u32,
     bounce_depth: u16,
+    integrator: Arc<dyn Integrator>,
+    adaptive: Option<AdaptiveSampling>,
+}
+
+/// The result of rendering a scene, see [Renderer::render]
+#[derive(Debug, Clone)]
+pub struct RenderOutput {
+    /// Unclamped linear radiance of each pixel
+    pub image: Rgb32FImage,
+    /// Auxiliary passes of the image, if captured
+    pub aovs: Option<Aovs>,
+    /// Number of samples spent on each pixel
+    pub sample_counts: ImageBuffer<Luma<u32>, Vec<u32>>,
 }
 
 impl Renderer {
     /// Creates a new [Renderer].
+    ///
+    /// Renders with a [PathTracer] by default.
     pub fn new(
         image_width: u32,
         image_height: u32,
@@ -29,22 +64,38 @@ impl Renderer {
             image_height,
             samples_per_pixel,
             bounce_depth,
+            integrator: Arc::new(PathTracer::default()),
+            adaptive: None,
         }
     }
 
-    /// Calculates the total color value of the pixel at image coordinates (`x`, `y`)
+    /// Sets the [Integrator] computing the color seen along each ray
+    pub fn with_integrator(mut self, integrator: &Arc<dyn Integrator>) -> Self {
+        self.integrator = Arc::clone(integrator);
+        self
+    }
+
+    /// Samples each pixel adaptively, up to the samples per pixel, see [AdaptiveSampling]
+    pub fn with_adaptive_sampling(mut self, adaptive: AdaptiveSampling) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    /// Calculates the color value of a sample of the pixel at image coordinates (`x`, `y`)
     ///
     /// Uses the provided [Camera] to translate the image coordinates
-    /// to world space coordinates, then computes the color value
+    /// to world space coordinates, then computes the color value.
+    /// Also returns the auxiliary values of the sample, if `capture_aovs` is set.
     #[inline]
     fn compute_pixel_v(
         &self,
         cam: &Camera,
-        world: &impl Hittable,
+        context: &RenderContext,
         x: u32,
         y: u32,
-        rng: &mut impl Rng,
-    ) -> Vec3A {
+        capture_aovs: bool,
+        rng: &mut SmallRng,
+    ) -> (Vec3A, AovSample) {
         // convert buffer indices to viewport coordinates
         let offset_u: f32 = rng.gen();
         let offset_v: f32 = rng.gen();
@@ -53,60 +104,194 @@ impl Renderer {
             ((self.image_height - y) as f32 + offset_v) as f64 / (self.image_height - 1) as f64;
 
         // trace ray
-        let contrib =
-            cam.get_ray(u as f32, v as f32, rng)
-                .shade(world, self.bounce_depth, cam.bg_color, rng);
-        Vec3A::from(contrib)
+        let ray = cam.get_ray(u as f32, v as f32, rng);
+        let contrib = self.integrator.radiance(&ray, context, rng);
+
+        let aov = if capture_aovs {
+            let rec = context.world.hit(&ray, 0.001, f32::INFINITY);
+            AovSample::new(&ray, rec.as_ref(), context.bg_color)
+        } else {
+            AovSample::default()
+        };
+        (Vec3A::from(contrib), aov)
     }
 
     /// Generates an image from the given scene.
     ///
     /// A scene consists of a [Camera] and some [Hittable].
+    /// The emissive objects of the scene that support it are sampled as lights.
     /// This functions outputs its progress to the commandline.
-    pub fn render_scene(&self, scene: (Camera, impl Hittable)) -> image::RgbImage {
-        let progress_bar = get_progressbar((self.image_height * self.image_width) as u64)
-            .with_prefix("Generating pixels");
+    /// The image is displayed with the default [DisplayTransform].
+    pub fn render_scene(&self, scene: (Camera, impl Hittable)) -> RgbImage {
+        DisplayTransform::default().apply(&self.render_scene_linear(scene))
+    }
+
+    /// Like [Renderer::render_scene], also capturing the auxiliary passes of the image
+    pub fn render_scene_with_aovs(&self, scene: (Camera, impl Hittable)) -> (RgbImage, Aovs) {
+        let (linear, aovs) = self.render_scene_linear_with_aovs(scene);
+        (DisplayTransform::default().apply(&linear), aovs)
+    }
+
+    /// Like [Renderer::render_scene], keeping the unclamped linear radiance of each pixel
+    pub fn render_scene_linear(&self, scene: (Camera, impl Hittable)) -> Rgb32FImage {
+        self.render(scene, false).image
+    }
+
+    /// Like [Renderer::render_scene_linear], also capturing the auxiliary passes of the image
+    pub fn render_scene_linear_with_aovs(
+        &self,
+        scene: (Camera, impl Hittable),
+    ) -> (Rgb32FImage, Aovs) {
+        let output = self.render(scene, true);
+        (
+            output.image,
+            output.aovs.expect("auxiliary passes are captured"),
+        )
+    }
+
+    /// Generates the linear radiance of each pixel of the given scene, along with the samples spent on it.
+    ///
+    /// Also captures the auxiliary passes of the image, if `capture_aovs` is set.
+    /// Pixels are sampled in batches with adaptive sampling, and all at once otherwise.
+    pub fn render(&self, scene: (Camera, impl Hittable), capture_aovs: bool) -> RenderOutput {
+        let batch_size = match self.adaptive {
+            Some(adaptive) => adaptive.batch_size,
+            None => self.samples_per_pixel,
+        };
+        let estimates = self.render_passes(scene, capture_aovs, batch_size, |_| ());
+        self.collect_output(&estimates, capture_aovs)
+    }
+
+    /// Like [Renderer::render], refining the whole image one sample pass at a time.
+    ///
+    /// Each pass takes a sample of every pixel, or a batch of samples with adaptive sampling.
+    /// The current estimate is handed to `on_snapshot` whenever `schedule` says so,
+    /// so that it can be kept in case the render is stopped early.
+    pub fn render_progressive(
+        &self,
+        scene: (Camera, impl Hittable),
+        capture_aovs: bool,
+        schedule: SnapshotSchedule,
+        mut on_snapshot: impl FnMut(&RenderOutput),
+    ) -> RenderOutput {
+        let batch_size = match self.adaptive {
+            Some(adaptive) => adaptive.batch_size,
+            None => 1,
+        };
+        let (mut passes, mut last_snapshot) = (0, Instant::now());
+        let estimates = self.render_passes(scene, capture_aovs, batch_size, |estimates| {
+            passes += 1;
+            if schedule.is_due(passes, last_snapshot.elapsed()) {
+                on_snapshot(&self.collect_output(estimates, capture_aovs));
+                (passes, last_snapshot) = (0, Instant::now());
+            }
+        });
+        self.collect_output(&estimates, capture_aovs)
+    }
+
+    /// Samples each pixel of the given scene in passes of `batch_size` samples, until each one is done.
+    ///
+    /// Calls `on_pass` with the estimates of the pixels after each pass, and returns them once done.
+    fn render_passes(
+        &self,
+        scene: (Camera, impl Hittable),
+        capture_aovs: bool,
+        batch_size: u32,
+        mut on_pass: impl FnMut(&[(PixelEstimate, AovSample)]),
+    ) -> Vec<(PixelEstimate, AovSample)> {
+        let pixel_count = (self.image_height * self.image_width) as usize;
+        let progress_bar = get_progressbar(pixel_count as u64 * self.samples_per_pixel as u64)
+            .with_prefix("Generating samples");
 
         // Set up rendering properties
         let (cam, world) = scene;
+        let mut lights = HittableList::new();
+        world.collect_lights(&mut lights);
+        let context = RenderContext {
+            world: &world,
+            lights: &lights,
+            bg_color: cam.bg_color,
+            bounce_depth: self.bounce_depth,
+        };
+        let batch_size = batch_size.max(1);
+
+        // Generate pixels, row by row, a batch of samples at a time until each one is done
+        let mut estimates = vec![(PixelEstimate::default(), AovSample::default()); pixel_count];
+        loop {
+            let sampled_pixels: usize = estimates
+                .par_iter_mut()
+                .enumerate()
+                .filter(|(_, (estimate, _))| !self.is_pixel_done(estimate))
+                .map(|(idx, (estimate, aov))| {
+                    let (x, y) = (idx as u32 % self.image_width, idx as u32 / self.image_width);
+                    let batch = batch_size.min(self.samples_per_pixel - estimate.samples);
+
+                    // map reduce the batch of samples into a single estimate
+                    let (batch_estimate, batch_aov) = (0..batch)
+                        .into_par_iter()
+                        .map_init(
+                            || SmallRng::from_rng(rand::thread_rng()),
+                            // current sample # doesn't matter, ignore
+                            |rng, _| {
+                                // from_rng(...) gives Result, unpack here
+                                let rng = rng.as_mut().unwrap();
+                                let (color_v, aov) =
+                                    self.compute_pixel_v(&cam, &context, x, y, capture_aovs, rng);
+                                (PixelEstimate::new(color_v), aov)
+                            },
+                        )
+                        .reduce(
+                            || (PixelEstimate::default(), AovSample::default()),
+                            |a, b| (a.0.merge(b.0), a.1.merge(b.1)),
+                        );
+
+                    *estimate = estimate.merge(batch_estimate);
+                    *aov = aov.merge(batch_aov);
+                    progress_bar.inc(batch as u64);
+                    1
+                })
+                .sum();
+            if sampled_pixels == 0 {
+                break;
+            }
+            on_pass(&estimates);
+        }
+        progress_bar.finish_using_style();
+
+        estimates
+    }
+
+    /// Gathers the image, auxiliary passes and sample counts from the estimates of each pixel
+    fn collect_output(
+        &self,
+        estimates: &[(PixelEstimate, AovSample)],
+        capture_aovs: bool,
+    ) -> RenderOutput {
+        // Allocate framebuffer, accounting for number of samples
+        let image = Rgb32FImage::from_fn(self.image_width, self.image_height, |x, y| {
+            let (estimate, _) = estimates[(y * self.image_width + x) as usize];
+            Rgb(estimate.mean().to_array())
+        });
+        let sample_counts = ImageBuffer::from_fn(self.image_width, self.image_height, |x, y| {
+            Luma([estimates[(y * self.image_width + x) as usize].0.samples])
+        });
+        let aovs = capture_aovs.then(|| {
+            let aov_samples: Vec<AovSample> = estimates.iter().map(|(_, aov)| *aov).collect();
+            Aovs::from_samples(self.image_width, self.image_height, &aov_samples)
+        });
+
+        RenderOutput {
+            image,
+            aovs,
+            sample_counts,
+        }
+    }
 
-        // Allocate image buffer
-        let mut img_buf: image::RgbImage =
-            image::ImageBuffer::new(self.image_width, self.image_height);
-
-        // Generate image
-        img_buf
-            .enumerate_pixels_mut()
-            .par_bridge()
-            .progress_with(progress_bar)
-            .for_each(|indexed_pixel| {
-                // unpack the enumeration
-                let (x, y, pixel) = indexed_pixel;
-
-                // map reduce N samples into single Vec3A
-                let mut color_v = (0..self.samples_per_pixel)
-                    .into_par_iter()
-                    .map_init(
-                        || SmallRng::from_rng(rand::thread_rng()),
-                        // current sample # doesn't matter, ignore
-                        |rng, _| {
-                            // from_rng(...) gives Result, unpack here
-                            let rng = rng.as_mut().unwrap();
-                            self.compute_pixel_v(&cam, &world, x, y, rng)
-                        },
-                    )
-                    .reduce(|| Vec3A::ZERO, |a, b| a + b);
-
-                // Account for number of samples
-                color_v /= self.samples_per_pixel as f32;
-
-                // "gamma" correction
-                color_v = color_v.powf(0.5); // sqrt
-
-                // modify pixel with generated color value
-                *pixel = image::Rgb::<u8>::from(Color::new(color_v));
-            });
-
-        img_buf
+    /// Returns whether a pixel needs no more samples
+    fn is_pixel_done(&self, estimate: &PixelEstimate) -> bool {
+        estimate.samples >= self.samples_per_pixel
+            || self
+                .adaptive
+                .is_some_and(|adaptive| estimate.relative_error() < adaptive.noise_threshold)
     }
 }
@@ -0,0 +1,184 @@
+//! Adaptive sampling, spending more samples on the noisier pixels of an image
+
+use glam::Vec3A;
+use image::{ImageBuffer, Luma, Rgb, RgbImage};
+
+use super::tonemap::luminance;
+use crate::color::Color;
+
+/// Luminance below which pixels count as black when judging their noise
+const MIN_LUMINANCE: f32 = 1e-2;
+
+/// Settings of adaptive sampling
+///
+/// Pixels are sampled in batches, until the estimate of their color is precise enough,
+/// or the [Renderer](super::Renderer)'s samples per pixel are spent.
+/// An estimate is precise enough when the 95% confidence interval of its luminance,
+/// relative to the luminance itself, is narrower than the noise threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSampling {
+    /// Relative half-width of the confidence interval below which a pixel is done
+    pub noise_threshold: f32,
+    /// Number of samples taken for a pixel between judging its noise
+    pub batch_size: u32,
+}
+
+impl AdaptiveSampling {
+    /// Creates new [AdaptiveSampling] settings, with batches of 16 samples
+    pub fn new(noise_threshold: f32) -> Self {
+        Self {
+            noise_threshold,
+            batch_size: 16,
+        }
+    }
+
+    /// Sets the number of samples taken for a pixel between judging its noise
+    pub fn with_batch_size(mut self, batch_size: u32) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+}
+
+/// Running estimate of a pixel's color, from the samples taken so far
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PixelEstimate {
+    /// Sum of the sampled colors
+    sum: Vec3A,
+    /// Sum of the sampled luminances
+    luminance_sum: f32,
+    /// Sum of the squares of the sampled luminances
+    luminance_sq_sum: f32,
+    /// Number of samples
+    pub(crate) samples: u32,
+}
+
+impl PixelEstimate {
+    /// Creates the estimate of a single sampled color
+    pub(crate) fn new(color: Vec3A) -> Self {
+        let l = luminance(color);
+        Self {
+            sum: color,
+            luminance_sum: l,
+            luminance_sq_sum: l * l,
+            samples: 1,
+        }
+    }
+
+    /// Combines the estimates of two sets of samples
+    pub(crate) fn merge(self, other: Self) -> Self {
+        Self {
+            sum: self.sum + other.sum,
+            luminance_sum: self.luminance_sum + other.luminance_sum,
+            luminance_sq_sum: self.luminance_sq_sum + other.luminance_sq_sum,
+            samples: self.samples + other.samples,
+        }
+    }
+
+    /// Returns the mean of the sampled colors
+    pub(crate) fn mean(&self) -> Vec3A {
+        self.sum / self.samples.max(1) as f32
+    }
+
+    /// Returns the half-width of the 95% confidence interval of the mean luminance, relative to it
+    pub(crate) fn relative_error(&self) -> f32 {
+        if self.samples < 2 {
+            return f32::INFINITY;
+        }
+        let n = self.samples as f32;
+        let mean = self.luminance_sum / n;
+        // unbiased sample variance, clamped against rounding errors
+        let variance = ((self.luminance_sq_sum - n * mean * mean) / (n - 1.0)).max(0.0);
+        1.96 * (variance / n).sqrt() / mean.max(MIN_LUMINANCE)
+    }
+}
+
+/// Returns a heatmap of the samples spent on each pixel, from blue for none to red for `max_samples`
+pub fn sample_heatmap(
+    sample_counts: &ImageBuffer<Luma<u32>, Vec<u32>>,
+    max_samples: u32,
+) -> RgbImage {
+    ImageBuffer::from_fn(sample_counts.width(), sample_counts.height(), |x, y| {
+        let t = sample_counts.get_pixel(x, y).0[0] as f32 / max_samples.max(1) as f32;
+        // hue from 240° down to 0°, at full saturation and value
+        let hue = 4.0 * (1.0 - t.clamp(0.0, 1.0));
+        let channel = |n: f32| {
+            let k = (n + hue) % 6.0;
+            1.0 - k.min(4.0 - k).clamp(0.0, 1.0)
+        };
+        Rgb::<u8>::from(Color::new(Vec3A::new(
+            channel(5.0),
+            channel(3.0),
+            channel(1.0),
+        )))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        camera::Camera,
+        hittables::{Hittable, HittableList, Sphere},
+        material::Material,
+        render::Renderer,
+    };
+
+    #[test]
+    fn error_shrinks_with_samples() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut estimate_noisy = |samples: u32| {
+            (0..samples)
+                .map(|_| PixelEstimate::new(Vec3A::splat(rng.gen_range(0.0..1.0))))
+                .fold(PixelEstimate::default(), PixelEstimate::merge)
+        };
+
+        // uniform luminances in 0..1 have a standard deviation of 1/sqrt(12), around a mean of 0.5
+        let expected = |n: f32| 1.96 * (1.0 / 12.0 / n).sqrt() / 0.5;
+        for samples in [64, 1024, 16384] {
+            let error = estimate_noisy(samples).relative_error();
+            assert!(
+                (error - expected(samples as f32)).abs() < 0.1 * expected(samples as f32),
+                "{} samples: {}",
+                samples,
+                error
+            );
+        }
+
+        // a constant color is known exactly once its variance can be estimated
+        let constant = PixelEstimate::new(Vec3A::ONE).merge(PixelEstimate::new(Vec3A::ONE));
+        assert_eq!(constant.relative_error(), 0.0);
+        assert_eq!(
+            PixelEstimate::new(Vec3A::ONE).relative_error(),
+            f32::INFINITY
+        );
+    }
+
+    #[test]
+    fn stops_sampling_constant_pixels() {
+        let grey = Arc::new(Material::Lambertian {
+            albedo: Arc::new(Color::new(Vec3A::splat(0.5))),
+        });
+        // a sphere on the ground, lit by a constant sky
+        let world: HittableList = vec![
+            Sphere::new(Vec3A::new(0.0, -101.0, -2.0), 100.0, &grey).wrap(),
+            Sphere::new(Vec3A::new(0.0, 0.0, -2.0), 1.0, &grey).wrap(),
+        ];
+        let mut cam = Camera::default();
+        cam.bg_color = Color::new(Vec3A::splat(0.5));
+        let renderer = Renderer::new(8, 6, 64, 4)
+            .with_adaptive_sampling(AdaptiveSampling::new(0.01).with_batch_size(8));
+
+        let output = renderer.render((cam, world), false);
+
+        // the top left corner only sees the sky, known exactly after a single batch
+        assert_eq!(output.sample_counts.get_pixel(0, 0).0[0], 8);
+        assert_eq!(output.image.get_pixel(0, 0).0, [0.5; 3]);
+        // while the light bouncing between the sphere and the ground stays noisy
+        assert!(output.sample_counts.pixels().any(|count| count.0[0] == 64));
+        assert!(output.sample_counts.pixels().all(|count| count.0[0] <= 64));
+    }
+}
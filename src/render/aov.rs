@@ -0,0 +1,285 @@
+//! Auxiliary render passes (AOVs), captured from the first surface seen through each pixel
+
+use std::{collections::HashMap, path::Path};
+
+use glam::Vec3A;
+use image::{ImageBuffer, ImageResult, Luma, Rgb, Rgb32FImage, RgbImage};
+
+use super::{is_hdr_path, save_linear, srgb_encode, DisplayTransform};
+use crate::{color::Color, hittables::HitRecord, ray::Ray};
+
+/// Possible auxiliary passes to choose from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::clap_derive::ArgEnum)]
+pub enum AovType {
+    /// Base color of the material, see [crate::Material::albedo]
+    Albedo,
+    /// Surface normal facing the camera
+    Normal,
+    /// Distance from the camera
+    Depth,
+    /// Position in world space
+    Position,
+    /// Identifier of the material
+    Id,
+}
+
+impl AovType {
+    /// Returns the name of the pass, as used in file names
+    pub fn name(self) -> &'static str {
+        match self {
+            AovType::Albedo => "albedo",
+            AovType::Normal => "normal",
+            AovType::Depth => "depth",
+            AovType::Position => "position",
+            AovType::Id => "id",
+        }
+    }
+}
+
+/// The auxiliary passes of a rendered image
+///
+/// Each pixel averages the samples whose camera rays hit a surface.
+/// Pixels without any have the background color as albedo, a zero normal and position,
+/// an infinite depth and an ID of 0.
+#[derive(Debug, Clone)]
+pub struct Aovs {
+    pub albedo: Rgb32FImage,
+    pub normal: Rgb32FImage,
+    pub position: Rgb32FImage,
+    pub depth: ImageBuffer<Luma<f32>, Vec<f32>>,
+    /// IDs are numbered from 1 by the order materials first appear in, row by row
+    pub id: ImageBuffer<Luma<u32>, Vec<u32>>,
+}
+
+impl Aovs {
+    /// Creates the passes of a `width` by `height` image from the samples of each pixel, row by row
+    pub(crate) fn from_samples(width: u32, height: u32, samples: &[AovSample]) -> Self {
+        let mut aovs = Self {
+            albedo: ImageBuffer::new(width, height),
+            normal: ImageBuffer::new(width, height),
+            position: ImageBuffer::new(width, height),
+            depth: ImageBuffer::new(width, height),
+            id: ImageBuffer::new(width, height),
+        };
+
+        let mut ids = HashMap::new();
+        for (idx, sample) in samples.iter().enumerate() {
+            let (x, y) = (idx as u32 % width, idx as u32 / width);
+            let hits = sample.hits.max(1) as f32;
+            let (normal, depth) = if sample.hits == 0 {
+                (Vec3A::ZERO, f32::INFINITY)
+            } else {
+                (sample.normal.normalize_or_zero(), sample.depth / hits)
+            };
+            let id = match sample.material {
+                Some(material) => {
+                    let next_id = ids.len() as u32 + 1;
+                    *ids.entry(material).or_insert(next_id)
+                }
+                None => 0,
+            };
+
+            aovs.albedo
+                .put_pixel(x, y, Rgb((sample.albedo / hits).to_array()));
+            aovs.normal.put_pixel(x, y, Rgb(normal.to_array()));
+            aovs.position
+                .put_pixel(x, y, Rgb((sample.position / hits).to_array()));
+            aovs.depth.put_pixel(x, y, Luma([depth]));
+            aovs.id.put_pixel(x, y, Luma([id]));
+        }
+
+        aovs
+    }
+
+    /// Returns a viewable 8-bit image of a pass
+    ///
+    /// Normals are mapped from -1..1 to 0..1, depth from white up close to black at the farthest surface,
+    /// positions from the bounds of the surfaces seen to 0..1, and IDs to arbitrary colors.
+    pub fn to_rgb8(&self, aov: AovType) -> RgbImage {
+        let (width, height) = self.albedo.dimensions();
+        let to_rgb8 = |value: Vec3A| Rgb::<u8>::from(Color::new(value));
+
+        match aov {
+            // same transfer function as the beauty pass, albedos being within 0..=1 already
+            AovType::Albedo => ImageBuffer::from_fn(width, height, |x, y| {
+                let albedo = Vec3A::from(self.albedo.get_pixel(x, y).0);
+                to_rgb8(srgb_encode(albedo.clamp(Vec3A::ZERO, Vec3A::ONE)))
+            }),
+            AovType::Normal => ImageBuffer::from_fn(width, height, |x, y| {
+                to_rgb8((Vec3A::from(self.normal.get_pixel(x, y).0) + 1.0) / 2.0)
+            }),
+            AovType::Depth => {
+                let max_depth = self
+                    .depth
+                    .pixels()
+                    .map(|p| p.0[0])
+                    .filter(|d| d.is_finite())
+                    .fold(0.0, f32::max);
+                ImageBuffer::from_fn(width, height, |x, y| {
+                    let depth = self.depth.get_pixel(x, y).0[0];
+                    to_rgb8(Vec3A::splat(1.0 - depth / max_depth))
+                })
+            }
+            AovType::Position => {
+                let hit_positions = self
+                    .position
+                    .enumerate_pixels()
+                    .filter(|(x, y, _)| self.id.get_pixel(*x, *y).0[0] != 0)
+                    .map(|(_, _, p)| Vec3A::from(p.0));
+                let (min, max) = hit_positions.fold(
+                    (Vec3A::splat(f32::INFINITY), Vec3A::splat(f32::NEG_INFINITY)),
+                    |(min, max), p| (min.min(p), max.max(p)),
+                );
+                let size = (max - min).max(Vec3A::splat(f32::EPSILON));
+                ImageBuffer::from_fn(width, height, |x, y| match self.id.get_pixel(x, y).0[0] {
+                    0 => to_rgb8(Vec3A::ZERO),
+                    _ => to_rgb8((Vec3A::from(self.position.get_pixel(x, y).0) - min) / size),
+                })
+            }
+            AovType::Id => ImageBuffer::from_fn(width, height, |x, y| {
+                let id = self.id.get_pixel(x, y).0[0];
+                if id == 0 {
+                    return to_rgb8(Vec3A::ZERO);
+                }
+                // spread consecutive ids around the color wheel by the golden angle,
+                // at full saturation and value
+                let hue = (id as f32 * 0.618_034).fract() * 6.0;
+                let channel = |n: f32| {
+                    let k = (n + hue) % 6.0;
+                    1.0 - k.min(4.0 - k).clamp(0.0, 1.0)
+                };
+                to_rgb8(Vec3A::new(channel(5.0), channel(3.0), channel(1.0)))
+            }),
+        }
+    }
+
+    /// Returns the values of a pass as they are, for high dynamic range formats
+    ///
+    /// Single channel passes are repeated over all three channels.
+    /// Pixels without any surface have a depth of 0 here, as infinity doesn't survive every format.
+    pub fn to_rgb32f(&self, aov: AovType) -> Rgb32FImage {
+        let (width, height) = self.albedo.dimensions();
+        match aov {
+            AovType::Albedo => self.albedo.clone(),
+            AovType::Normal => self.normal.clone(),
+            AovType::Position => self.position.clone(),
+            AovType::Depth => ImageBuffer::from_fn(width, height, |x, y| {
+                let depth = self.depth.get_pixel(x, y).0[0];
+                Rgb([if depth.is_finite() { depth } else { 0.0 }; 3])
+            }),
+            AovType::Id => ImageBuffer::from_fn(width, height, |x, y| {
+                Rgb([self.id.get_pixel(x, y).0[0] as f32; 3])
+            }),
+        }
+    }
+
+    /// Writes a pass to `path`, in the format named by its extension
+    ///
+    /// High dynamic range formats receive the values as they are (see [Aovs::to_rgb32f]),
+    /// other formats a viewable 8-bit image (see [Aovs::to_rgb8]).
+    pub fn save(&self, aov: AovType, path: impl AsRef<Path>) -> ImageResult<()> {
+        if is_hdr_path(&path) {
+            save_linear(&self.to_rgb32f(aov), path, &DisplayTransform::default())
+        } else {
+            self.to_rgb8(aov).save(path)
+        }
+    }
+}
+
+/// Totals of the auxiliary values of a pixel's samples
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct AovSample {
+    albedo: Vec3A,
+    normal: Vec3A,
+    position: Vec3A,
+    depth: f32,
+    /// Number of samples hitting a surface
+    hits: u32,
+    /// Address of the material of the first sample hitting a surface
+    material: Option<usize>,
+}
+
+impl AovSample {
+    /// Creates the auxiliary values of a camera ray, hitting the surface of `rec` if any
+    pub(crate) fn new(ray: &Ray, rec: Option<&HitRecord>, bg_color: Color) -> Self {
+        match rec {
+            Some(rec) => Self {
+                albedo: rec.material.albedo(rec.u, rec.v, rec.point).into(),
+                normal: rec.normal,
+                position: rec.point,
+                depth: rec.t * ray.direction.length(),
+                hits: 1,
+                material: Some(std::sync::Arc::as_ptr(&rec.material) as usize),
+            },
+            None => Self {
+                albedo: bg_color.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Combines the totals of two sets of samples, keeping the material of the first to hit anything
+    pub(crate) fn merge(self, other: Self) -> Self {
+        // the background only counts as albedo where no sample hits anything
+        let albedo = match (self.hits, other.hits) {
+            (_, 0) => self.albedo,
+            (0, _) => other.albedo,
+            _ => self.albedo + other.albedo,
+        };
+        Self {
+            albedo,
+            normal: self.normal + other.normal,
+            position: self.position + other.position,
+            depth: self.depth + other.depth,
+            hits: self.hits + other.hits,
+            material: if self.hits > 0 {
+                self.material
+            } else {
+                other.material
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        hittables::{Hittable, Sphere},
+        material::Material,
+    };
+
+    #[test]
+    fn averages_samples_and_numbers_materials() {
+        let glass = Arc::new(Material::Dielectric { refract_index: 1.5 });
+        let sphere = Sphere::new(Vec3A::ZERO, 1.0, &glass);
+        let bg_color = Color::new(Vec3A::new(0.1, 0.2, 0.3));
+
+        let hit_ray = Ray::new(Vec3A::new(0.0, 0.0, 5.0), -Vec3A::Z * 2.0, 0.0);
+        let miss_ray = Ray::new(Vec3A::new(0.0, 5.0, 5.0), -Vec3A::Z, 0.0);
+        let sample = |ray: &Ray| {
+            AovSample::new(
+                ray,
+                sphere.hit(ray, 0.001, f32::INFINITY).as_ref(),
+                bg_color,
+            )
+        };
+
+        // a pixel partly covered by the sphere, then one missing it entirely
+        let covered = sample(&miss_ray).merge(sample(&hit_ray).merge(sample(&hit_ray)));
+        let missed = sample(&miss_ray).merge(sample(&miss_ray));
+        let aovs = Aovs::from_samples(2, 1, &[covered, missed]);
+
+        assert_eq!(aovs.albedo.get_pixel(0, 0).0, [1.0; 3]);
+        assert_eq!(aovs.normal.get_pixel(0, 0).0, [0.0, 0.0, 1.0]);
+        assert_eq!(aovs.position.get_pixel(0, 0).0, [0.0, 0.0, 1.0]);
+        assert_eq!(aovs.depth.get_pixel(0, 0).0, [4.0]);
+        assert_eq!(aovs.id.get_pixel(0, 0).0, [1]);
+
+        assert_eq!(aovs.albedo.get_pixel(1, 0).0, [0.1, 0.2, 0.3]);
+        assert_eq!(aovs.depth.get_pixel(1, 0).0, [f32::INFINITY]);
+        assert_eq!(aovs.id.get_pixel(1, 0).0, [0]);
+    }
+}
@@ -0,0 +1,197 @@
+//! Edge-aware denoising of rendered images, guided by their auxiliary passes
+//!
+//! Implements the edge-avoiding à-trous wavelet filter of Dammertz et al.,
+//! "Edge-Avoiding À-Trous Wavelet Transform for fast Global Illumination Filtering" (2010):
+//! a 5x5 blur is repeated with taps spread twice as far apart each iteration,
+//! weighting each tap down the more its color, albedo, normal or depth differs from the filtered pixel's.
+
+use glam::Vec3A;
+use image::{Rgb, Rgb32FImage};
+use rayon::prelude::*;
+
+use super::Aovs;
+
+/// Weights of the B3 spline kernel, separated into its rows and columns
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Edge-aware denoiser, see the [module-level documentation](self)
+///
+/// Each `*_sigma` controls how strongly differences in that quantity stop the blur:
+/// a smaller sigma keeps more edges, and more noise along with them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Denoiser {
+    /// Number of filter passes, covering a width of `4 * 2^iterations - 3` pixels
+    pub iterations: u32,
+    /// Tolerance to differences in color, compressed by `c / (1 + c)`; halved each iteration
+    pub color_sigma: f32,
+    /// Tolerance to differences in albedo
+    pub albedo_sigma: f32,
+    /// Tolerance to differences in normal
+    pub normal_sigma: f32,
+    /// Tolerance to differences in depth, relative to the depth and the distance between pixels
+    pub depth_sigma: f32,
+}
+
+impl Denoiser {
+    /// Creates a new [Denoiser] running `iterations` passes, with default tolerances
+    pub fn new(iterations: u32) -> Self {
+        Self {
+            iterations,
+            ..Default::default()
+        }
+    }
+
+    /// Filters an image of linear radiance, guided by the auxiliary passes rendered with it
+    pub fn apply(&self, linear: &Rgb32FImage, aovs: &Aovs) -> Rgb32FImage {
+        let (width, height) = linear.dimensions();
+        let mut color_sigma = self.color_sigma;
+        let mut current = linear.clone();
+
+        for iteration in 0..self.iterations {
+            let step = 1_i64 << iteration;
+            let filtered: Vec<f32> = (0..width * height)
+                .into_par_iter()
+                .flat_map_iter(|idx| {
+                    let (x, y) = (idx % width, idx / width);
+                    self.filter_pixel(&current, aovs, x, y, step, color_sigma)
+                        .to_array()
+                })
+                .collect();
+            current = Rgb32FImage::from_raw(width, height, filtered)
+                .expect("filtered buffer matches the image dimensions");
+            color_sigma /= 2.0;
+        }
+
+        current
+    }
+
+    /// Returns the weighted average of the 5x5 taps around pixel (`x`, `y`), `step` pixels apart
+    fn filter_pixel(
+        &self,
+        image: &Rgb32FImage,
+        aovs: &Aovs,
+        x: u32,
+        y: u32,
+        step: i64,
+        color_sigma: f32,
+    ) -> Vec3A {
+        let (width, height) = image.dimensions();
+        let guide = Guide::new(image, aovs, x, y);
+
+        let mut sum = Vec3A::ZERO;
+        let mut total_weight = 0.0;
+        for (j, ky) in KERNEL.iter().enumerate() {
+            for (i, kx) in KERNEL.iter().enumerate() {
+                let (dx, dy) = ((i as i64 - 2) * step, (j as i64 - 2) * step);
+                let (qx, qy) = (x as i64 + dx, y as i64 + dy);
+                if qx < 0 || qy < 0 || qx >= width as i64 || qy >= height as i64 {
+                    continue;
+                }
+                let other = Guide::new(image, aovs, qx as u32, qy as u32);
+                let distance = ((dx * dx + dy * dy) as f32).sqrt().max(1.0);
+
+                let weight = kx * ky * self.edge_weight(&guide, &other, distance, color_sigma);
+                sum += weight * other.color;
+                total_weight += weight;
+            }
+        }
+
+        // the center tap always has a weight of at least the kernel's, no division by zero
+        sum / total_weight
+    }
+
+    /// Returns how much a tap `distance` pixels away contributes to the filtered pixel, in `0..=1`
+    fn edge_weight(&self, pixel: &Guide, tap: &Guide, distance: f32, color_sigma: f32) -> f32 {
+        let compress = |c: Vec3A| c / (1.0 + c);
+        let color_diff = (compress(pixel.color) - compress(tap.color)).length_squared();
+        let albedo_diff = (pixel.albedo - tap.albedo).length_squared();
+        let normal_diff = (pixel.normal - tap.normal).length_squared();
+        let depth_diff = match (pixel.depth.is_finite(), tap.depth.is_finite()) {
+            // both showing the background
+            (false, false) => 0.0,
+            (true, true) => (pixel.depth - tap.depth).abs() / (pixel.depth.max(1e-4) * distance),
+            _ => f32::INFINITY,
+        };
+
+        (-color_diff / (color_sigma * color_sigma)
+            - albedo_diff / (self.albedo_sigma * self.albedo_sigma)
+            - normal_diff / (self.normal_sigma * self.normal_sigma)
+            - depth_diff * depth_diff / (self.depth_sigma * self.depth_sigma))
+            .exp()
+    }
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            color_sigma: 1.0,
+            albedo_sigma: 0.1,
+            normal_sigma: 0.3,
+            depth_sigma: 0.05,
+        }
+    }
+}
+
+/// Values of a pixel guiding the filter
+struct Guide {
+    color: Vec3A,
+    albedo: Vec3A,
+    normal: Vec3A,
+    depth: f32,
+}
+
+impl Guide {
+    fn new(image: &Rgb32FImage, aovs: &Aovs, x: u32, y: u32) -> Self {
+        let Rgb(color) = *image.get_pixel(x, y);
+        Self {
+            color: Vec3A::from(color),
+            albedo: Vec3A::from(aovs.albedo.get_pixel(x, y).0),
+            normal: Vec3A::from(aovs.normal.get_pixel(x, y).0),
+            depth: aovs.depth.get_pixel(x, y).0[0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Luma};
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn smooths_noise_within_edges() {
+        // two flat grey surfaces facing different ways, split down the middle, rendered with noise
+        let (width, height) = (32, 32);
+        let mut rng = SmallRng::seed_from_u64(0);
+        let facing_right = |x: u32| x >= width / 2;
+        let linear = Rgb32FImage::from_fn(width, height, |x, _| {
+            let mean = if facing_right(x) { 0.8 } else { 0.2 };
+            Rgb([mean * rng.gen_range(0.0..2.0); 3])
+        });
+        let aovs = Aovs {
+            albedo: ImageBuffer::from_pixel(width, height, Rgb([0.5; 3])),
+            normal: ImageBuffer::from_fn(width, height, |x, _| match facing_right(x) {
+                true => Rgb([1.0, 0.0, 0.0]),
+                false => Rgb([0.0, 0.0, 1.0]),
+            }),
+            position: ImageBuffer::new(width, height),
+            depth: ImageBuffer::from_pixel(width, height, Luma([1.0])),
+            id: ImageBuffer::new(width, height),
+        };
+
+        let denoised = Denoiser::default().apply(&linear, &aovs);
+
+        // noise is gone, and neither side bleeds into the other
+        for (x, _, pixel) in denoised.enumerate_pixels() {
+            let expected = if facing_right(x) { 0.8 } else { 0.2 };
+            assert!(
+                (pixel.0[0] - expected).abs() < 0.1 * expected,
+                "{} at column {}",
+                pixel.0[0],
+                x
+            );
+        }
+    }
+}
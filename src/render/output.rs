@@ -0,0 +1,85 @@
+//! Writing rendered images to files
+
+use std::{fs::File, io::BufWriter, path::Path};
+
+use image::{codecs::hdr::HdrEncoder, ImageResult, Rgb, Rgb32FImage};
+
+use super::DisplayTransform;
+
+/// Returns whether the extension of `path` names a high dynamic range format, `.exr` or `.hdr`
+pub fn is_hdr_path(path: impl AsRef<Path>) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exr") || ext.eq_ignore_ascii_case("hdr"))
+}
+
+/// Writes an image of linear values to `path`, in the format named by its extension
+///
+/// High dynamic range formats (see [is_hdr_path]) receive the values as they are,
+/// other formats the viewable 8-bit image given by `display`.
+/// Radiance `.hdr` files cannot hold negative values, which are written as 0.
+pub fn save_linear(
+    linear: &Rgb32FImage,
+    path: impl AsRef<Path>,
+    display: &DisplayTransform,
+) -> ImageResult<()> {
+    let path = path.as_ref();
+    if !is_hdr_path(path) {
+        return display.apply(linear).save(path);
+    }
+
+    let is_radiance = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("hdr"));
+    if is_radiance {
+        // image's generic saving doesn't support Radiance files, encode them directly
+        let writer = BufWriter::new(File::create(path)?);
+        let pixels: Vec<Rgb<f32>> = linear.pixels().copied().collect();
+        HdrEncoder::new(writer).encode(&pixels, linear.width() as usize, linear.height() as usize)
+    } else {
+        linear.save(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hdr_files_keep_unclamped_values() {
+        let linear = Rgb32FImage::from_fn(2, 1, |x, _| match x {
+            0 => Rgb([0.25, 1.0, 4.0]),
+            _ => Rgb([16.0, 0.0, 0.5]),
+        });
+        let dir = std::env::temp_dir();
+
+        for ext in ["exr", "hdr"] {
+            let path = dir.join(format!("lustre-output-test-{}.{}", std::process::id(), ext));
+            save_linear(&linear, &path, &DisplayTransform::default()).unwrap();
+            // image's generic loading clamps Radiance files to 8 bits, decode them directly
+            let read: Vec<Rgb<f32>> = if ext == "hdr" {
+                let reader = std::io::BufReader::new(File::open(&path).unwrap());
+                image::codecs::hdr::HdrDecoder::new(reader)
+                    .and_then(|decoder| decoder.read_image_hdr())
+                    .unwrap()
+            } else {
+                image::open(&path)
+                    .unwrap()
+                    .into_rgb32f()
+                    .pixels()
+                    .copied()
+                    .collect()
+            };
+            std::fs::remove_file(&path).unwrap();
+
+            // Radiance files share an exponent between channels, exact here as values are powers of 2
+            for (read, written) in read.iter().zip(linear.pixels()) {
+                assert_eq!(read, written, "mismatch in .{} file", ext);
+            }
+        }
+
+        assert!(is_hdr_path("out.EXR"));
+        assert!(!is_hdr_path("out.png"));
+    }
+}
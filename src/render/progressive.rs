@@ -0,0 +1,79 @@
+//! Progressive rendering, handing out the current estimate of an image while it is being refined
+
+use std::time::Duration;
+
+/// When a progressive render hands out its current estimate, see [Renderer::render_progressive]
+///
+/// A snapshot is due once either limit is reached since the previous one.
+/// Without any limit, only the finished image is produced.
+///
+/// [Renderer::render_progressive]: super::Renderer::render_progressive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SnapshotSchedule {
+    /// Number of sample passes between snapshots
+    pub passes: Option<u32>,
+    /// Rendering time between snapshots, checked after each pass
+    pub interval: Option<Duration>,
+}
+
+impl SnapshotSchedule {
+    /// Creates a new [SnapshotSchedule], taking a snapshot every `passes` passes
+    pub fn every_passes(passes: u32) -> Self {
+        Self {
+            passes: Some(passes),
+            interval: None,
+        }
+    }
+
+    /// Creates a new [SnapshotSchedule], taking a snapshot every `interval` of rendering time
+    pub fn every_interval(interval: Duration) -> Self {
+        Self {
+            passes: None,
+            interval: Some(interval),
+        }
+    }
+
+    /// Returns whether a snapshot is due, `passes` passes and `elapsed` time after the previous one
+    pub fn is_due(&self, passes: u32, elapsed: Duration) -> bool {
+        self.passes.is_some_and(|limit| passes >= limit)
+            || self.interval.is_some_and(|limit| elapsed >= limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glam::Vec3A;
+
+    use super::*;
+    use crate::{
+        camera::Camera,
+        color::Color,
+        hittables::{Hittable, HittableList, Sphere},
+        material::Material,
+        render::Renderer,
+    };
+
+    #[test]
+    fn snapshots_every_few_passes() {
+        let grey = Arc::new(Material::Lambertian {
+            albedo: Arc::new(Color::new(Vec3A::splat(0.5))),
+        });
+        let world: HittableList = vec![Sphere::new(Vec3A::new(0.0, 0.0, -2.0), 1.0, &grey).wrap()];
+        let renderer = Renderer::new(4, 3, 6, 4);
+
+        let mut snapshot_samples = Vec::new();
+        let output = renderer.render_progressive(
+            (Camera::default(), world),
+            false,
+            SnapshotSchedule::every_passes(2),
+            |snapshot| snapshot_samples.push(snapshot.sample_counts.get_pixel(0, 0).0[0]),
+        );
+
+        // one sample per pass, until all samples are taken
+        assert_eq!(snapshot_samples, [2, 4, 6]);
+        assert!(output.sample_counts.pixels().all(|count| count.0[0] == 6));
+        assert!(output.aovs.is_none());
+    }
+}
@@ -0,0 +1,191 @@
+//! Display transform turning linear radiance into viewable colors
+//!
+//! Applied after rendering, in three steps:
+//! 1. exposure - scales radiance by a power of 2
+//! 2. tone mapping - compresses the scaled radiance into the displayable range, see [ToneMapper]
+//! 3. sRGB transfer function - encodes the result for 8-bit images
+
+use glam::Vec3A;
+use image::{ImageBuffer, Rgb, Rgb32FImage, RgbImage};
+
+use crate::color::Color;
+
+/// Possible tone mapping operators to choose from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::clap_derive::ArgEnum)]
+pub enum ToneMapper {
+    /// Clips values above 1, as if no tone mapping took place
+    Clamp,
+    /// Reinhard's operator `L / (1 + L)` on luminance, never reaching white
+    Reinhard,
+    /// Reinhard's operator with a luminance mapped to white, see [DisplayTransform::white_point]
+    ExtendedReinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve
+    Aces,
+    /// John Hable's filmic curve from Uncharted 2, with a linear white point, see [DisplayTransform::white_point]
+    Uncharted2,
+}
+
+impl ToneMapper {
+    /// Maps exposed linear radiance into the range `0..=1`, with `white_point` mapped to 1 where it applies
+    pub fn map(self, color: Vec3A, white_point: f32) -> Vec3A {
+        let mapped = match self {
+            ToneMapper::Clamp => color,
+            ToneMapper::Reinhard => scale_luminance(color, |l| l / (1.0 + l)),
+            ToneMapper::ExtendedReinhard => {
+                let white_sq = white_point * white_point;
+                scale_luminance(color, |l| l * (1.0 + l / white_sq) / (1.0 + l))
+            }
+            ToneMapper::Aces => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                (color * (a * color + b)) / (color * (c * color + d) + e)
+            }
+            ToneMapper::Uncharted2 => {
+                // curve parameters as presented by Hable,
+                // who also raises the exposure by a stop beforehand, left to the exposure setting here
+                let curve = |x: Vec3A| {
+                    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+                    (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f
+                };
+                curve(color) / curve(Vec3A::splat(white_point))
+            }
+        };
+        mapped.clamp(Vec3A::ZERO, Vec3A::ONE)
+    }
+
+    /// Returns the white point used when none is given, for the linear radiance of `image`
+    ///
+    /// This is the brightest luminance of the image for [ToneMapper::ExtendedReinhard],
+    /// and Hable's 11.2 for [ToneMapper::Uncharted2]. Other operators ignore the white point.
+    fn default_white_point(self, image: &Rgb32FImage, exposure_scale: f32) -> f32 {
+        match self {
+            ToneMapper::ExtendedReinhard => image
+                .pixels()
+                .map(|p| luminance(exposure_scale * Vec3A::from(p.0)))
+                .filter(|l| l.is_finite())
+                .fold(f32::EPSILON, f32::max),
+            ToneMapper::Uncharted2 => 11.2,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Transform from the linear radiance of a rendered image to displayable 8-bit colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayTransform {
+    /// Operator compressing radiance into the displayable range
+    pub tone_mapper: ToneMapper,
+    /// Exposure value, in stops: radiance is scaled by `2^exposure` before tone mapping
+    pub exposure: f32,
+    /// Exposed radiance mapped to white by the operators using one
+    ///
+    /// If `None`, chosen per operator, see [ToneMapper::ExtendedReinhard] and [ToneMapper::Uncharted2]
+    pub white_point: Option<f32>,
+}
+
+impl DisplayTransform {
+    /// Creates a new [DisplayTransform], choosing the white point per operator
+    pub fn new(tone_mapper: ToneMapper, exposure: f32) -> Self {
+        Self {
+            tone_mapper,
+            exposure,
+            white_point: None,
+        }
+    }
+
+    /// Sets the exposed radiance mapped to white
+    pub fn with_white_point(mut self, white_point: f32) -> Self {
+        self.white_point = Some(white_point);
+        self
+    }
+
+    /// Converts an image of linear radiance into a viewable 8-bit sRGB image
+    pub fn apply(&self, linear: &Rgb32FImage) -> RgbImage {
+        let exposure_scale = self.exposure.exp2();
+        let white_point = self
+            .white_point
+            .unwrap_or_else(|| self.tone_mapper.default_white_point(linear, exposure_scale));
+
+        ImageBuffer::from_fn(linear.width(), linear.height(), |x, y| {
+            let color_v = exposure_scale * Vec3A::from(linear.get_pixel(x, y).0);
+            let color_v = self.tone_mapper.map(color_v, white_point);
+            Rgb::<u8>::from(Color::new(srgb_encode(color_v)))
+        })
+    }
+}
+
+impl Default for DisplayTransform {
+    /// Clamps radiance at its rendered exposure
+    fn default() -> Self {
+        Self::new(ToneMapper::Clamp, 0.0)
+    }
+}
+
+/// Applies the sRGB transfer function to linear values in `0..=1`
+pub fn srgb_encode(linear: Vec3A) -> Vec3A {
+    let encode = |c: f32| {
+        if c <= 0.003_130_8 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    };
+    Vec3A::new(encode(linear.x), encode(linear.y), encode(linear.z))
+}
+
+/// Returns the relative luminance of a linear color, with Rec. 709 primaries
+pub(crate) fn luminance(color: Vec3A) -> f32 {
+    color.dot(Vec3A::new(0.2126, 0.7152, 0.0722))
+}
+
+/// Scales a color to the luminance given by `curve`, keeping its hue
+fn scale_luminance(color: Vec3A, curve: impl Fn(f32) -> f32) -> Vec3A {
+    let l = luminance(color);
+    if l > 0.0 {
+        color * (curve(l) / l)
+    } else {
+        Vec3A::ZERO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_into_display_range() {
+        let operators = [
+            ToneMapper::Clamp,
+            ToneMapper::Reinhard,
+            ToneMapper::ExtendedReinhard,
+            ToneMapper::Aces,
+            ToneMapper::Uncharted2,
+        ];
+        for op in operators {
+            // black stays black, brighter radiance never maps darker
+            assert!(op.map(Vec3A::ZERO, 4.0).abs_diff_eq(Vec3A::ZERO, 1e-3));
+            let mut previous = 0.0;
+            for step in 1..100 {
+                let mapped = op.map(Vec3A::splat(step as f32 * 0.1), 4.0);
+                assert!(
+                    mapped.max_element() <= 1.0 && mapped.x >= previous,
+                    "{:?}",
+                    op
+                );
+                previous = mapped.x;
+            }
+        }
+
+        // the white point maps to white
+        let white = Vec3A::splat(4.0);
+        assert!(ToneMapper::ExtendedReinhard
+            .map(white, 4.0)
+            .abs_diff_eq(Vec3A::ONE, 1e-5));
+        assert!(ToneMapper::Uncharted2
+            .map(white, 4.0)
+            .abs_diff_eq(Vec3A::ONE, 1e-5));
+
+        // sRGB curve endpoints and mid grey
+        assert!(srgb_encode(Vec3A::ONE).abs_diff_eq(Vec3A::ONE, 1e-5));
+        assert!((srgb_encode(Vec3A::splat(0.18)).x - 0.4614).abs() < 1e-3);
+    }
+}
@@ -15,6 +15,19 @@ pub fn reflect(v: Vec3A, n: Vec3A) -> Vec3A {
 pub fn refract(uv: Vec3A, n: Vec3A, eta_ratio: f32) -> Vec3A {
     let cos_theta = (-uv).dot(n).min(1.0);
     let r_perp = eta_ratio * (uv + cos_theta * n);
-    let r_para = (1.0 - r_perp.length_squared()).abs().sqrt() * -1.0 * n;
+    let r_para = -(1.0 - r_perp.length_squared()).abs().sqrt() * n;
     r_perp + r_para
 }
+
+/// Returns the weight of a sample taken with `pdf`, out of two strategies that could have taken it,
+/// for multiple importance sampling
+///
+/// Uses Veach's power heuristic, with an exponent of 2.
+pub fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    if pdf <= 0.0 {
+        return 0.0;
+    }
+    // dividing first keeps infinite densities, from grazing angles, from turning into NaN
+    let ratio = other_pdf / pdf;
+    1.0 / (1.0 + ratio * ratio)
+}
@@ -6,9 +6,14 @@ use glam::{UVec2, Vec3A};
 use rand::Rng;
 
 use crate::{
-    bvh::BvhNode, camera::Camera, color::Color, hittables::*, material::Material, textures::*,
+    bounds::BoundingBox, bvh::LinearBvh, camera::Camera, color::Color, hittables::*,
+    material::Material, textures::*,
 };
 
+pub use description::*;
+
+pub mod description;
+
 /// Possible hard-coded scenes to choose from.
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy, clap::clap_derive::ArgEnum)]
@@ -33,6 +38,8 @@ pub enum SceneType {
     RandomLights,
     /// The Final Scene from Ray Tracing in One Weekend: The Next Week
     FinalScene,
+    /// Procedural shapes rendered from signed distance functions
+    SdfShapes,
 }
 
 /// Returns a [Camera], a list of objects ([HittableList]), and the image dimensions as a tuple.
@@ -42,30 +49,21 @@ pub fn get_scene(
     rng: &mut impl Rng,
 ) -> (Camera, HittableList, UVec2) {
     // Setup default camera properties
-    // uncomment the `mut` once its needed
-    let mut aspect_ratio = 16.0 / 9.0;
-    let mut look_from = Vec3A::new(13.0, 2.0, 3.0);
-    let mut look_at = Vec3A::ZERO;
-    let /* mut */ view_up = Vec3A::Y;
-    let mut vert_fov = 20.0;
-    let mut aperture = 0.0;
-    let mut focus_dist = 10.0;
-    let /* mut */ shutter_time = 0.0..1.0;
-    let mut bg_color = Color::new(Vec3A::new(0.7, 0.8, 1.0));
+    let mut cam = CameraDescription::default();
 
     // Grabs the scene and changes any cam params
     let scene = match scene_type {
         SceneType::MaterialDev => {
-            aspect_ratio = 16.0 / 9.0;
-            look_from = Vec3A::ZERO;
-            look_at = -Vec3A::Z;
-            focus_dist = 1.0;
-            vert_fov = 90.0;
+            cam.aspect_ratio = 16.0 / 9.0;
+            cam.look_from = Vec3A::ZERO;
+            cam.look_at = -Vec3A::Z;
+            cam.focus_dist = 1.0;
+            cam.vert_fov = 90.0;
             get_mat_dev_scene()
         }
         SceneType::CoverPhoto => {
-            aperture = 0.1;
-            aspect_ratio = 3.0 / 2.0;
+            cam.aperture = 0.1;
+            cam.aspect_ratio = 3.0 / 2.0;
             gen_random_scene(rng)
         }
 
@@ -73,58 +71,53 @@ pub fn get_scene(
         SceneType::TwoPerlinSpheres => gen_two_perlin_spheres(),
         SceneType::Earth => gen_earth(),
         SceneType::SimpleLight => {
-            bg_color = Color::new(Vec3A::ZERO);
-            look_from = Vec3A::new(26.0, 3.0, 6.0);
-            look_at = Vec3A::new(0.0, 2.0, 0.0);
+            cam.bg_color = Color::new(Vec3A::ZERO);
+            cam.look_from = Vec3A::new(26.0, 3.0, 6.0);
+            cam.look_at = Vec3A::new(0.0, 2.0, 0.0);
             gen_simple_light()
         }
         SceneType::CornellBox => {
-            aspect_ratio = 1.0;
-            bg_color = Color::new(Vec3A::ZERO);
-            look_from = Vec3A::new(278.0, 278.0, -800.0);
-            look_at = Vec3A::new(278.0, 278.0, 0.0);
-            vert_fov = 40.0;
+            cam.aspect_ratio = 1.0;
+            cam.bg_color = Color::new(Vec3A::ZERO);
+            cam.look_from = Vec3A::new(278.0, 278.0, -800.0);
+            cam.look_at = Vec3A::new(278.0, 278.0, 0.0);
+            cam.vert_fov = 40.0;
             gen_cornell_box()
         }
         SceneType::CornellBox2 => {
-            aspect_ratio = 1.0;
-            bg_color = Color::new(Vec3A::ZERO);
-            look_from = Vec3A::new(278.0, 278.0, -800.0);
-            look_at = Vec3A::new(278.0, 278.0, 0.0);
-            vert_fov = 40.0;
+            cam.aspect_ratio = 1.0;
+            cam.bg_color = Color::new(Vec3A::ZERO);
+            cam.look_from = Vec3A::new(278.0, 278.0, -800.0);
+            cam.look_at = Vec3A::new(278.0, 278.0, 0.0);
+            cam.vert_fov = 40.0;
             gen_cornell_box2()
         }
         SceneType::RandomLights => {
-            aperture = 0.1;
-            aspect_ratio = 3.0 / 2.0;
-            bg_color = Color::new(Vec3A::from(bg_color) / 10.0);
+            cam.aperture = 0.1;
+            cam.aspect_ratio = 3.0 / 2.0;
+            cam.bg_color = Color::new(Vec3A::from(cam.bg_color) / 10.0);
             gen_emissive_random(rng)
         }
         SceneType::FinalScene => {
-            aspect_ratio = 1.0;
-            bg_color = Color::new(Vec3A::ZERO);
-            look_from = Vec3A::new(478.0, 278.0, -600.0);
-            look_at = Vec3A::new(278.0, 278.0, 0.0);
-            vert_fov = 40.0;
+            cam.aspect_ratio = 1.0;
+            cam.bg_color = Color::new(Vec3A::ZERO);
+            cam.look_from = Vec3A::new(478.0, 278.0, -600.0);
+            cam.look_at = Vec3A::new(278.0, 278.0, 0.0);
+            cam.vert_fov = 40.0;
             gen_book2_scene(rng)
         }
+        SceneType::SdfShapes => {
+            cam.aspect_ratio = 16.0 / 9.0;
+            cam.look_from = Vec3A::new(0.0, 2.5, 8.0);
+            cam.look_at = Vec3A::new(0.0, 1.0, 0.0);
+            cam.vert_fov = 35.0;
+            gen_sdf_shapes()
+        }
     };
 
     // set up camera with (possibly modified) properies
-    let cam = Camera::new(
-        look_from,
-        look_at,
-        view_up,
-        vert_fov,
-        aspect_ratio,
-        aperture,
-        focus_dist,
-        shutter_time,
-        bg_color,
-    );
-
-    let image_height = (image_width as f32 / aspect_ratio) as u32;
-    let dimensions = UVec2::new(image_width, image_height);
+    let dimensions = cam.dimensions(image_width);
+    let cam = cam.build();
 
     (cam, scene, dimensions)
 }
@@ -251,7 +244,7 @@ fn gen_two_spheres() -> HittableList {
 /// Returns a [HittableList] containing two Perlin noise spheres.
 fn gen_two_perlin_spheres() -> HittableList {
     let perlin_tex = Arc::new(Material::Lambertian {
-        albedo: Arc::new(NoiseTexture::new(::noise::Perlin::new(), 4.0)),
+        albedo: Arc::new(NoiseTexture::new(::noise::Perlin::default(), 4.0)),
     });
 
     vec![
@@ -270,6 +263,70 @@ fn gen_earth() -> HittableList {
     vec![globe.wrap()]
 }
 
+/// Returns a [HittableList] with a rounded box, two smoothly joined spheres, and a Mandelbulb
+fn gen_sdf_shapes() -> HittableList {
+    let ground_material = Arc::new(Material::Lambertian {
+        albedo: Arc::new(SolidColor::new(Vec3A::splat(0.5))),
+    });
+    let red = Arc::new(Material::Lambertian {
+        albedo: Arc::new(SolidColor::new(Vec3A::new(0.7, 0.1, 0.1))),
+    });
+    let glass = Arc::new(Material::Dielectric { refract_index: 1.5 });
+    let gold = Arc::new(Material::Metal {
+        albedo: Arc::new(SolidColor::new(Vec3A::new(0.8, 0.6, 0.2))),
+        roughness: 0.1,
+    });
+
+    let rounded_box: Arc<dyn Sdf> = Arc::new(RoundedBox {
+        center: Vec3A::new(-2.5, 0.8, 0.0),
+        half_extents: Vec3A::splat(0.8),
+        radius: 0.2,
+    });
+    let rounded_box = SdfObject::new(
+        &rounded_box,
+        BoundingBox::new(Vec3A::new(-3.3, 0.0, -0.8), Vec3A::new(-1.7, 1.6, 0.8)),
+        &red,
+    );
+
+    // bounded by the two spheres, grown by the smoothness
+    let blob: Arc<dyn Sdf> = Arc::new(SmoothUnion {
+        a: Arc::new(SdfSphere {
+            center: Vec3A::new(-0.3, 0.7, 0.0),
+            radius: 0.7,
+        }),
+        b: Arc::new(SdfSphere {
+            center: Vec3A::new(0.4, 1.4, 0.0),
+            radius: 0.5,
+        }),
+        smoothness: 0.4,
+    });
+    let blob = SdfObject::new(
+        &blob,
+        BoundingBox::new(Vec3A::new(-1.4, -0.4, -1.1), Vec3A::new(1.3, 2.3, 1.1)),
+        &glass,
+    );
+
+    let mandelbulb: Arc<dyn Sdf> = Arc::new(Mandelbulb {
+        center: Vec3A::new(2.5, 1.1, 0.0),
+        scale: 1.0,
+        power: 8.0,
+        iterations: 12,
+    });
+    let mandelbulb = SdfObject::new(
+        &mandelbulb,
+        BoundingBox::new(Vec3A::new(1.3, -0.1, -1.2), Vec3A::new(3.7, 2.3, 1.2)),
+        &gold,
+    )
+    .with_epsilon(0.001);
+
+    vec![
+        Plane::new(Vec3A::ZERO, Vec3A::Y, &ground_material).wrap(),
+        rounded_box.wrap(),
+        blob.wrap(),
+        mandelbulb.wrap(),
+    ]
+}
+
 /// Returns a [HittableList] resembling [gen_two_perlin_spheres], with a rectangular diffuse light
 fn gen_simple_light() -> HittableList {
     let diff_light = Arc::new(Material::DiffuseLight {
@@ -634,7 +691,8 @@ fn gen_book2_scene(rng: &mut impl Rng) -> HittableList {
     }
 
     // BVH-ify the ground boxes
-    let mut all_objects: HittableList = vec![BvhNode::new(ground_boxes, 0.0, 1.0, rng).wrap()];
+    let ground_bvh = LinearBvh::new(ground_boxes, 0.0, 1.0).expect("ground boxes are non-empty");
+    let mut all_objects: HittableList = vec![ground_bvh.wrap()];
 
     let light_mat = Arc::new(Material::DiffuseLight {
         albedo: Arc::new(SolidColor::new(Vec3A::ONE)),
@@ -712,7 +770,7 @@ fn gen_book2_scene(rng: &mut impl Rng) -> HittableList {
 
     // perlin noise sphere
     let perlin_mat = Arc::new(Material::Lambertian {
-        albedo: Arc::new(NoiseTexture::new(::noise::Perlin::new(), 0.1)),
+        albedo: Arc::new(NoiseTexture::new(::noise::Perlin::default(), 0.1)),
     });
 
     // group of white spheres
@@ -727,7 +785,9 @@ fn gen_book2_scene(rng: &mut impl Rng) -> HittableList {
         })
         .collect();
 
-    let wrapped_spheres: Arc<dyn Hittable> = BvhNode::new(rand_sphere_group, 0.0, 1.0, rng).wrap();
+    let wrapped_spheres: Arc<dyn Hittable> = LinearBvh::new(rand_sphere_group, 0.0, 1.0)
+        .expect("sphere group is non-empty")
+        .wrap();
     all_objects.push(
         Transform::new(&wrapped_spheres)
             .with_axis_angle(glam::Vec3::Y, 15.0)
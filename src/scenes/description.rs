@@ -0,0 +1,1304 @@
+//! Declarative scene descriptions
+//!
+//! Instead of being hardcoded as a [SceneType](super::SceneType),
+//! a scene can be described in a [RON](https://github.com/ron-rs/ron) or JSON file.
+//! A description holds the camera properties, a set of named materials,
+//! named prototype objects shared between [Instance]s, and the objects making up the scene.
+//!
+//! Existing scenes can also be described and written back out into a scene file,
+//! see [describe_scene] and [save_scene_file].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use glam::{UVec2, Vec2, Vec3, Vec3A};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bounds::BoundingBox, bvh::LinearBvh, camera::Camera, color::Color, hittables::*,
+    material::Material, textures::*,
+};
+
+/// A complete scene, as read from a scene file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    /// The camera viewing the scene
+    #[serde(default)]
+    pub camera: CameraDescription,
+    /// Materials available to the scene's objects, referred to by name
+    #[serde(default)]
+    pub materials: BTreeMap<String, MaterialDescription>,
+    /// Objects built once and shared by every [Instance] referring to them by name
+    ///
+    /// Prototypes aren't part of the scene by themselves, and can't contain instances.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prototypes: BTreeMap<String, ObjectDescription>,
+    /// The objects making up the scene
+    pub objects: Vec<ObjectDescription>,
+}
+
+/// Properties of a [Camera]
+///
+/// Any missing properties take on the same defaults used by the hardcoded scenes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraDescription {
+    pub look_from: Vec3A,
+    pub look_at: Vec3A,
+    pub view_up: Vec3A,
+    /// Vertical field of view, in degrees
+    pub vert_fov: f32,
+    pub aspect_ratio: f32,
+    pub aperture: f32,
+    pub focus_dist: f32,
+    pub shutter_time: Range<f32>,
+    pub bg_color: Color,
+}
+
+/// Description of a [Material]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum MaterialDescription {
+    Lambertian {
+        albedo: TextureDescription,
+    },
+    Metal {
+        albedo: TextureDescription,
+        roughness: f32,
+    },
+    Dielectric {
+        refract_index: f32,
+    },
+    DiffuseLight {
+        albedo: TextureDescription,
+        brightness: f32,
+    },
+    Isotropic {
+        albedo: TextureDescription,
+    },
+}
+
+/// Description of a [Texture]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum TextureDescription {
+    /// A [SolidColor]
+    SolidColor(Color),
+    /// A [Checkered] texture alternating between two other textures
+    Checkered {
+        odd: Box<TextureDescription>,
+        even: Box<TextureDescription>,
+    },
+    /// An [ImageMap], relative paths are resolved against the scene file's directory
+    Image { path: PathBuf },
+    /// A [NoiseTexture] using Perlin noise
+    Perlin { scale: f32 },
+}
+
+/// Description of one of the built-in [Sdf] shapes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum SdfDescription {
+    /// A [SdfSphere]
+    Sphere { center: Vec3A, radius: f32 },
+    /// A [RoundedBox]
+    RoundedBox {
+        center: Vec3A,
+        half_extents: Vec3A,
+        radius: f32,
+    },
+    /// A [SmoothUnion] of two other shapes
+    SmoothUnion {
+        a: Box<SdfDescription>,
+        b: Box<SdfDescription>,
+        smoothness: f32,
+    },
+    /// A [Mandelbulb] fractal
+    Mandelbulb {
+        center: Vec3A,
+        scale: f32,
+        power: f32,
+        iterations: u32,
+    },
+}
+
+/// Description of an object implementing [Hittable]
+///
+/// Objects refer to materials by their name in [SceneDescription::materials].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum ObjectDescription {
+    Sphere {
+        center: Vec3A,
+        radius: f32,
+        material: String,
+    },
+    MovingSphere {
+        center0: Vec3A,
+        center1: Vec3A,
+        time0: f32,
+        time1: f32,
+        radius: f32,
+        material: String,
+    },
+    /// A [Quad] defined by its four corners, in order
+    Quad {
+        p0: Vec3A,
+        p1: Vec3A,
+        p2: Vec3A,
+        p3: Vec3A,
+        material: String,
+    },
+    QuadBox {
+        min: Vec3A,
+        max: Vec3A,
+        material: String,
+    },
+    /// An infinite [Plane], textures repeat every `tile_size` units
+    Plane {
+        point: Vec3A,
+        normal: Vec3A,
+        #[serde(default = "default_tile_size")]
+        tile_size: f32,
+        material: String,
+    },
+    /// A [Disk] facing towards `normal`
+    Disk {
+        center: Vec3A,
+        normal: Vec3A,
+        radius: f32,
+        material: String,
+    },
+    /// A [Cylinder] from `p0` to `p1`, open at both ends unless `capped`
+    Cylinder {
+        p0: Vec3A,
+        p1: Vec3A,
+        radius: f32,
+        #[serde(default = "default_capped")]
+        capped: bool,
+        material: String,
+    },
+    /// A [Cone] from `p0` to `p1`, with the radius changing from `radius0` to `radius1`
+    Cone {
+        p0: Vec3A,
+        p1: Vec3A,
+        radius0: f32,
+        radius1: f32,
+        #[serde(default = "default_capped")]
+        capped: bool,
+        material: String,
+    },
+    /// A [Capsule] around the line from `p0` to `p1`
+    Capsule {
+        p0: Vec3A,
+        p1: Vec3A,
+        radius: f32,
+        material: String,
+    },
+    /// A [Torus] around the Y axis, use a [Transform] for other orientations
+    Torus {
+        center: Vec3A,
+        major_radius: f32,
+        minor_radius: f32,
+        material: String,
+    },
+    /// A [Triangle], with optional per-vertex normals and texture coordinates
+    Triangle {
+        p0: Vec3A,
+        p1: Vec3A,
+        p2: Vec3A,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normals: Option<[Vec3A; 3]>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        uvs: Option<[Vec2; 3]>,
+        material: String,
+    },
+    /// A [TriangleMesh] loaded from an OBJ file.
+    ///
+    /// Relative paths are resolved against the scene file's directory,
+    /// `material` is used for faces without a material in the OBJ file.
+    Mesh { path: PathBuf, material: String },
+    /// A [Heightfield] over a footprint of `size.x` by `size.z` units from `min`, up to `size.y` units high.
+    ///
+    /// Relative image paths are resolved against the scene file's directory.
+    Heightfield {
+        source: HeightfieldSource,
+        min: Vec3A,
+        size: Vec3A,
+        material: String,
+    },
+    /// A [Transform] of another object.
+    ///
+    /// Applies the scale, then the rotation, then the translation,
+    /// then any further operations in order.
+    Transform {
+        object: Box<ObjectDescription>,
+        #[serde(default = "default_scale", skip_serializing_if = "is_default_scale")]
+        scale: Vec3,
+        /// Rotation as an axis and an angle in degrees
+        #[serde(
+            default = "default_rotation",
+            skip_serializing_if = "is_default_rotation"
+        )]
+        rotation: (Vec3, f32),
+        #[serde(default, skip_serializing_if = "is_default_translation")]
+        translation: Vec3,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        operations: Vec<TransformOperation>,
+    },
+    /// An [Instance] of a named prototype, transformed by the operations in order.
+    ///
+    /// If given, `material` replaces the materials of the prototype.
+    Instance {
+        prototype: String,
+        #[serde(default)]
+        operations: Vec<TransformOperation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<String>,
+    },
+    /// An [AnimatedTransform] of another object, moving through the keyframes over time
+    AnimatedTransform {
+        object: Box<ObjectDescription>,
+        keyframes: Vec<Keyframe>,
+    },
+    /// A [SdfObject], within the bounding box from `min` to `max`
+    Sdf {
+        sdf: SdfDescription,
+        min: Vec3A,
+        max: Vec3A,
+        #[serde(default = "default_max_steps")]
+        max_steps: u32,
+        #[serde(default = "default_epsilon")]
+        epsilon: f32,
+        material: String,
+    },
+    /// A [Csg] combination of two closed objects
+    Csg {
+        operation: CsgOperation,
+        left: Box<ObjectDescription>,
+        right: Box<ObjectDescription>,
+    },
+    ConstantMedium {
+        boundary: Box<ObjectDescription>,
+        albedo: TextureDescription,
+        density: f32,
+    },
+    /// A [NonConstantMedium] whose density follows Perlin noise
+    NonConstantMedium {
+        boundary: Box<ObjectDescription>,
+        albedo: TextureDescription,
+        scale: f32,
+    },
+    /// A plain list of objects
+    List { objects: Vec<ObjectDescription> },
+    /// A list of objects placed into a [LinearBvh]
+    Bvh { objects: Vec<ObjectDescription> },
+}
+
+fn default_scale() -> Vec3 {
+    Vec3::ONE
+}
+
+fn default_rotation() -> (Vec3, f32) {
+    (Vec3::Y, 0.0)
+}
+
+fn is_default_scale(scale: &Vec3) -> bool {
+    *scale == default_scale()
+}
+
+fn is_default_rotation(rotation: &(Vec3, f32)) -> bool {
+    rotation.1 == 0.0
+}
+
+fn is_default_translation(translation: &Vec3) -> bool {
+    *translation == Vec3::ZERO
+}
+
+/// Default `tile_size` of a described [Plane]
+fn default_tile_size() -> f32 {
+    1.0
+}
+
+/// Default `capped` of a described [Cylinder] or [Cone]
+fn default_capped() -> bool {
+    true
+}
+
+/// Default `max_steps` of a described [SdfObject]
+fn default_max_steps() -> u32 {
+    256
+}
+
+/// Default `epsilon` of a described [SdfObject]
+fn default_epsilon() -> f32 {
+    0.0001
+}
+
+/// Errors possible when loading a scene file
+#[derive(Debug)]
+pub enum SceneFileError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The file extension does not match a supported format
+    UnsupportedFormat(Option<String>),
+    /// The file contents are not a valid scene description
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The description is well-formed, but refers to something invalid
+    Invalid { location: String, message: String },
+    /// The description could not be written out in the file format
+    Serialize(String),
+}
+
+impl Display for SceneFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneFileError::Io(e) => write!(f, "{}", e),
+            SceneFileError::UnsupportedFormat(ext) => write!(
+                f,
+                "unsupported scene file extension {:?}, expected \"ron\" or \"json\"",
+                ext.as_deref().unwrap_or_default()
+            ),
+            SceneFileError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            SceneFileError::Invalid { location, message } => {
+                write!(f, "{}: {}", location, message)
+            }
+            SceneFileError::Serialize(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {}
+
+impl From<std::io::Error> for SceneFileError {
+    fn from(e: std::io::Error) -> Self {
+        SceneFileError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for SceneFileError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        SceneFileError::Parse {
+            line: e.span.start.line,
+            column: e.span.start.col,
+            message: e.code.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for SceneFileError {
+    fn from(e: serde_json::Error) -> Self {
+        // serde_json appends the position to its messages, which is already reported separately
+        let message = e.to_string();
+        let suffix = format!(" at line {} column {}", e.line(), e.column());
+        SceneFileError::Parse {
+            line: e.line(),
+            column: e.column(),
+            message: message
+                .strip_suffix(&suffix)
+                .unwrap_or(&message)
+                .to_string(),
+        }
+    }
+}
+
+/// Returns a [Camera], a list of objects ([HittableList]), and the image dimensions as a tuple,
+/// as described by the scene file at `path`.
+///
+/// The file format is determined by the file extension, either `.ron` or `.json`.
+pub fn load_scene_file(
+    path: &Path,
+    image_width: u32,
+) -> Result<(Camera, HittableList, UVec2), SceneFileError> {
+    let contents = std::fs::read_to_string(path)?;
+    let extension = path.extension().and_then(|ext| ext.to_str());
+
+    let description: SceneDescription = match extension {
+        Some("ron") => ron::from_str(&contents)?,
+        Some("json") => serde_json::from_str(&contents)?,
+        _ => {
+            return Err(SceneFileError::UnsupportedFormat(
+                extension.map(String::from),
+            ))
+        }
+    };
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    description.build(image_width, base_dir)
+}
+
+/// Writes the scene description into a file at `path`.
+///
+/// The file format is determined by the file extension, either `.ron` or `.json`.
+pub fn save_scene_file(path: &Path, description: &SceneDescription) -> Result<(), SceneFileError> {
+    let extension = path.extension().and_then(|ext| ext.to_str());
+
+    let contents = match extension {
+        Some("ron") => ron::ser::to_string_pretty(description, ron::ser::PrettyConfig::default())
+            .map_err(|e| SceneFileError::Serialize(e.to_string()))?,
+        Some("json") => serde_json::to_string_pretty(description)
+            .map_err(|e| SceneFileError::Serialize(e.to_string()))?,
+        _ => {
+            return Err(SceneFileError::UnsupportedFormat(
+                extension.map(String::from),
+            ))
+        }
+    };
+
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Returns a description of the scene made up of the given [Camera] and objects.
+///
+/// Any random placements are described as they were resolved during scene generation.
+/// File paths are written relative to `base_dir`, the directory the description is saved into.
+pub fn describe_scene(
+    cam: &Camera,
+    world: &HittableList,
+    base_dir: &Path,
+) -> Result<SceneDescription, SceneFileError> {
+    let mut materials = MaterialNames::new(base_dir);
+
+    let objects = world
+        .iter()
+        .enumerate()
+        .map(|(idx, obj)| {
+            obj.describe(&mut materials)
+                .ok_or_else(|| SceneFileError::Invalid {
+                    location: format!("objects[{}]", idx),
+                    message: "object can't be described".to_string(),
+                })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(SceneDescription {
+        camera: cam.description().clone(),
+        materials: materials.materials,
+        prototypes: materials.prototypes,
+        objects,
+    })
+}
+
+/// Assigns names to the materials and instance prototypes of objects being described,
+/// and writes their file paths relative to the directory of the description
+#[derive(Debug, Default)]
+pub struct MaterialNames {
+    /// Absolute directory the description is saved into
+    base_dir: PathBuf,
+    /// Names of already described materials, keyed by their address
+    names: HashMap<*const Material, String>,
+    /// Descriptions of named materials
+    materials: BTreeMap<String, MaterialDescription>,
+    /// Names of already described prototypes, keyed by their address
+    prototype_names: HashMap<*const (), String>,
+    /// Descriptions of named prototypes
+    prototypes: BTreeMap<String, ObjectDescription>,
+}
+
+impl MaterialNames {
+    /// Creates new [MaterialNames], for a description saved into `base_dir`
+    pub fn new(base_dir: &Path) -> Self {
+        Self {
+            base_dir: absolute_path(base_dir),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the path of a file, as written into the description
+    ///
+    /// Paths are relative to the description's directory, as the loader resolves them against it,
+    /// so that descriptions don't depend on the machine or the directory they were written from.
+    pub fn path(&self, path: &Path) -> PathBuf {
+        let path = absolute_path(path);
+        let common = path
+            .components()
+            .zip(self.base_dir.components())
+            .take_while(|(a, b)| a == b)
+            .count();
+        // nothing in common, such as on another drive
+        if common == 0 {
+            return path;
+        }
+
+        let mut relative: PathBuf = self
+            .base_dir
+            .components()
+            .skip(common)
+            .map(|_| Component::ParentDir)
+            .collect();
+        relative.extend(path.components().skip(common));
+        relative
+    }
+
+    /// Returns the name of the material, describing it if it wasn't already.
+    ///
+    /// Returns `None` if the material can't be described.
+    pub fn name(&mut self, material: &Arc<Material>) -> Option<String> {
+        let key = Arc::as_ptr(material);
+        if let Some(name) = self.names.get(&key) {
+            return Some(name.clone());
+        }
+
+        let name = format!("material{}", self.names.len());
+        self.materials
+            .insert(name.clone(), material.describe(self)?);
+        self.names.insert(key, name.clone());
+        Some(name)
+    }
+
+    /// Returns the name of the prototype shared by [Instance]s, describing it if it wasn't already.
+    ///
+    /// Returns `None` if the prototype can't be described,
+    /// including when it holds instances itself, as prototypes are loaded without any others to refer to.
+    pub fn prototype(&mut self, prototype: &Arc<dyn Hittable>) -> Option<String> {
+        let key = Arc::as_ptr(prototype) as *const ();
+        if let Some(name) = self.prototype_names.get(&key) {
+            return Some(name.clone());
+        }
+
+        let name = format!("prototype{}", self.prototype_names.len());
+        let description = prototype.describe(self)?;
+        if description.has_instances() {
+            return None;
+        }
+        self.prototypes.insert(name.clone(), description);
+        self.prototype_names.insert(key, name.clone());
+        Some(name)
+    }
+}
+
+/// Returns `path` made absolute against the current directory, with `.` and `..` resolved
+///
+/// Resolved without touching the file system, as the path may not exist.
+fn absolute_path(path: &Path) -> PathBuf {
+    let path = match std::env::current_dir() {
+        Ok(dir) => dir.join(path),
+        Err(_) => path.to_path_buf(),
+    };
+
+    let mut absolute = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
+    }
+    absolute
+}
+
+/// Returns whether the noise function is [Perlin](::noise::Perlin) noise with the default seed,
+/// the only noise function supported by scene descriptions
+pub fn is_default_perlin<N: 'static>(noise: &N) -> bool {
+    use ::noise::Seedable;
+
+    match (noise as &dyn std::any::Any).downcast_ref::<::noise::Perlin>() {
+        Some(perlin) => perlin.seed() == ::noise::Perlin::DEFAULT_SEED,
+        None => false,
+    }
+}
+
+impl SceneDescription {
+    /// Builds the described scene.
+    ///
+    /// Relative texture paths are resolved against `base_dir`.
+    pub fn build(
+        &self,
+        image_width: u32,
+        base_dir: &Path,
+    ) -> Result<(Camera, HittableList, UVec2), SceneFileError> {
+        let materials = self
+            .materials
+            .iter()
+            .map(|(name, mat)| {
+                let material = mat.build(base_dir, &format!("materials[{:?}]", name))?;
+                Ok((name.as_str(), Arc::new(material)))
+            })
+            .collect::<Result<_, SceneFileError>>()?;
+
+        let mut ctx = BuildContext {
+            materials,
+            prototypes: BTreeMap::new(),
+            base_dir,
+            time0: self.camera.shutter_time.start,
+            time1: self.camera.shutter_time.end,
+        };
+
+        // prototypes are built without any other prototypes to refer to
+        ctx.prototypes = self
+            .prototypes
+            .iter()
+            .map(|(name, obj)| {
+                let prototype = obj.build(&ctx, format!("prototypes[{:?}]", name))?;
+                Ok((name.as_str(), prototype))
+            })
+            .collect::<Result<_, SceneFileError>>()?;
+
+        let world = self
+            .objects
+            .iter()
+            .enumerate()
+            .map(|(idx, obj)| obj.build(&ctx, format!("objects[{}]", idx)))
+            .collect::<Result<HittableList, _>>()?;
+
+        let cam = self.camera.build();
+        let dimensions = self.camera.dimensions(image_width);
+
+        Ok((cam, world, dimensions))
+    }
+}
+
+impl CameraDescription {
+    /// Creates the described [Camera]
+    pub fn build(&self) -> Camera {
+        Camera::new(
+            self.look_from,
+            self.look_at,
+            self.view_up,
+            self.vert_fov,
+            self.aspect_ratio,
+            self.aperture,
+            self.focus_dist,
+            self.shutter_time.clone(),
+            self.bg_color,
+        )
+    }
+
+    /// Returns the image dimensions for the given width, based on the aspect ratio
+    pub fn dimensions(&self, image_width: u32) -> UVec2 {
+        let image_height = (image_width as f32 / self.aspect_ratio) as u32;
+        UVec2::new(image_width, image_height)
+    }
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        Self {
+            look_from: Vec3A::new(13.0, 2.0, 3.0),
+            look_at: Vec3A::ZERO,
+            view_up: Vec3A::Y,
+            vert_fov: 20.0,
+            aspect_ratio: 16.0 / 9.0,
+            aperture: 0.0,
+            focus_dist: 10.0,
+            shutter_time: 0.0..1.0,
+            bg_color: Color::new(Vec3A::new(0.7, 0.8, 1.0)),
+        }
+    }
+}
+
+impl MaterialDescription {
+    /// Creates the described [Material]
+    ///
+    /// `location` describes where the material is in the scene description, for error reporting.
+    fn build(&self, base_dir: &Path, location: &str) -> Result<Material, SceneFileError> {
+        let albedo_location = format!("{}.albedo", location);
+        let material = match self {
+            MaterialDescription::Lambertian { albedo } => Material::Lambertian {
+                albedo: albedo.build(base_dir, &albedo_location)?,
+            },
+            MaterialDescription::Metal { albedo, roughness } => Material::Metal {
+                albedo: albedo.build(base_dir, &albedo_location)?,
+                roughness: *roughness,
+            },
+            MaterialDescription::Dielectric { refract_index } => Material::Dielectric {
+                refract_index: *refract_index,
+            },
+            MaterialDescription::DiffuseLight { albedo, brightness } => Material::DiffuseLight {
+                albedo: albedo.build(base_dir, &albedo_location)?,
+                brightness: *brightness,
+            },
+            MaterialDescription::Isotropic { albedo } => Material::Isotropic {
+                albedo: albedo.build(base_dir, &albedo_location)?,
+            },
+        };
+        Ok(material)
+    }
+}
+
+impl TextureDescription {
+    /// Creates the described [Texture]
+    ///
+    /// `location` describes where the texture is in the scene description, for error reporting.
+    fn build(&self, base_dir: &Path, location: &str) -> Result<Arc<dyn Texture>, SceneFileError> {
+        let texture: Arc<dyn Texture> = match self {
+            TextureDescription::SolidColor(color) => Arc::new(*color),
+            TextureDescription::Checkered { odd, even } => Arc::new(Checkered::new(
+                &odd.build(base_dir, &format!("{}.odd", location))?,
+                &even.build(base_dir, &format!("{}.even", location))?,
+            )),
+            TextureDescription::Image { path } => match ImageMap::load(base_dir.join(path)) {
+                Ok(image) => Arc::new(image),
+                Err(e) => {
+                    return Err(SceneFileError::Invalid {
+                        location: location.to_string(),
+                        message: format!("failed to load image {:?}: {}", path, e),
+                    })
+                }
+            },
+            TextureDescription::Perlin { scale } => {
+                Arc::new(NoiseTexture::new(::noise::Perlin::default(), *scale))
+            }
+        };
+        Ok(texture)
+    }
+}
+
+impl SdfDescription {
+    /// Creates the described [Sdf]
+    fn build(&self) -> Arc<dyn Sdf> {
+        match self {
+            SdfDescription::Sphere { center, radius } => Arc::new(SdfSphere {
+                center: *center,
+                radius: *radius,
+            }),
+            SdfDescription::RoundedBox {
+                center,
+                half_extents,
+                radius,
+            } => Arc::new(RoundedBox {
+                center: *center,
+                half_extents: *half_extents,
+                radius: *radius,
+            }),
+            SdfDescription::SmoothUnion { a, b, smoothness } => Arc::new(SmoothUnion {
+                a: a.build(),
+                b: b.build(),
+                smoothness: *smoothness,
+            }),
+            SdfDescription::Mandelbulb {
+                center,
+                scale,
+                power,
+                iterations,
+            } => Arc::new(Mandelbulb {
+                center: *center,
+                scale: *scale,
+                power: *power,
+                iterations: *iterations,
+            }),
+        }
+    }
+}
+
+/// Shared state used while building objects
+struct BuildContext<'a> {
+    materials: BTreeMap<&'a str, Arc<Material>>,
+    prototypes: BTreeMap<&'a str, Arc<dyn Hittable>>,
+    base_dir: &'a Path,
+    time0: f32,
+    time1: f32,
+}
+
+impl BuildContext<'_> {
+    /// Looks up a material by name
+    fn material(&self, name: &str, location: &str) -> Result<&Arc<Material>, SceneFileError> {
+        self.materials
+            .get(name)
+            .ok_or_else(|| SceneFileError::Invalid {
+                location: location.to_string(),
+                message: format!("unknown material {:?}", name),
+            })
+    }
+
+    /// Looks up a prototype by name
+    fn prototype(&self, name: &str, location: &str) -> Result<&Arc<dyn Hittable>, SceneFileError> {
+        self.prototypes
+            .get(name)
+            .ok_or_else(|| SceneFileError::Invalid {
+                location: location.to_string(),
+                message: format!("unknown prototype {:?}", name),
+            })
+    }
+}
+
+/// Checks the operations of a transform, normalizing rotation axes
+fn check_operations(
+    operations: &[TransformOperation],
+    location: &str,
+) -> Result<Vec<TransformOperation>, SceneFileError> {
+    operations
+        .iter()
+        .enumerate()
+        .map(|(idx, operation)| match *operation {
+            // rotations need a unit axis
+            TransformOperation::Rotate { axis, degrees } => {
+                if axis.length_squared() == 0.0 {
+                    return Err(SceneFileError::Invalid {
+                        location: format!("{}.operations[{}]", location, idx),
+                        message: "rotation axis must not be zero".to_string(),
+                    });
+                }
+                Ok(TransformOperation::Rotate {
+                    axis: axis.normalize(),
+                    degrees,
+                })
+            }
+            operation => Ok(operation),
+        })
+        .collect()
+}
+
+/// Checks that the ends of an object's axis are apart
+fn check_axis(p0: Vec3A, p1: Vec3A, location: &str) -> Result<(), SceneFileError> {
+    if p0 == p1 {
+        return Err(SceneFileError::Invalid {
+            location: location.to_string(),
+            message: "p0 and p1 must not be the same point".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks that a surface normal can be normalized
+fn check_normal(normal: Vec3A, location: &str) -> Result<(), SceneFileError> {
+    if normal.length_squared() == 0.0 {
+        return Err(SceneFileError::Invalid {
+            location: location.to_string(),
+            message: "normal must not be zero".to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl ObjectDescription {
+    /// Creates the described object.
+    ///
+    /// `location` describes where the object is in the scene description, for error reporting.
+    fn build(
+        &self,
+        ctx: &BuildContext,
+        location: String,
+    ) -> Result<Arc<dyn Hittable>, SceneFileError> {
+        let obj: Arc<dyn Hittable> = match self {
+            ObjectDescription::Sphere {
+                center,
+                radius,
+                material,
+            } => Sphere::new(*center, *radius, ctx.material(material, &location)?).wrap(),
+            ObjectDescription::MovingSphere {
+                center0,
+                center1,
+                time0,
+                time1,
+                radius,
+                material,
+            } => MovingSphere::new(
+                *center0,
+                *center1,
+                *time0,
+                *time1,
+                *radius,
+                ctx.material(material, &location)?,
+            )
+            .wrap(),
+            ObjectDescription::Quad {
+                p0,
+                p1,
+                p2,
+                p3,
+                material,
+            } => Quad::new(*p0, *p1, *p2, *p3, ctx.material(material, &location)?).wrap(),
+            ObjectDescription::QuadBox { min, max, material } => {
+                QuadBox::new(*min, *max, ctx.material(material, &location)?).wrap()
+            }
+            ObjectDescription::Plane {
+                point,
+                normal,
+                tile_size,
+                material,
+            } => {
+                check_normal(*normal, &location)?;
+                Plane::new(*point, *normal, ctx.material(material, &location)?)
+                    .with_tile_size(*tile_size)
+                    .wrap()
+            }
+            ObjectDescription::Disk {
+                center,
+                normal,
+                radius,
+                material,
+            } => {
+                check_normal(*normal, &location)?;
+                Disk::new(
+                    *center,
+                    *normal,
+                    *radius,
+                    ctx.material(material, &location)?,
+                )
+                .wrap()
+            }
+            ObjectDescription::Cylinder {
+                p0,
+                p1,
+                radius,
+                capped,
+                material,
+            } => {
+                check_axis(*p0, *p1, &location)?;
+                Cylinder::new(*p0, *p1, *radius, ctx.material(material, &location)?)
+                    .with_caps(*capped)
+                    .wrap()
+            }
+            ObjectDescription::Cone {
+                p0,
+                p1,
+                radius0,
+                radius1,
+                capped,
+                material,
+            } => {
+                check_axis(*p0, *p1, &location)?;
+                let material = ctx.material(material, &location)?;
+                Cone::new(*p0, *p1, *radius0, *radius1, material)
+                    .with_caps(*capped)
+                    .wrap()
+            }
+            ObjectDescription::Capsule {
+                p0,
+                p1,
+                radius,
+                material,
+            } => {
+                check_axis(*p0, *p1, &location)?;
+                Capsule::new(*p0, *p1, *radius, ctx.material(material, &location)?).wrap()
+            }
+            ObjectDescription::Torus {
+                center,
+                major_radius,
+                minor_radius,
+                material,
+            } => Torus::new(
+                *center,
+                *major_radius,
+                *minor_radius,
+                ctx.material(material, &location)?,
+            )
+            .wrap(),
+            ObjectDescription::Triangle {
+                p0,
+                p1,
+                p2,
+                normals,
+                uvs,
+                material,
+            } => {
+                let mut tri = Triangle::new(*p0, *p1, *p2, ctx.material(material, &location)?);
+                if let Some(normals) = normals {
+                    tri = tri.with_normals(*normals);
+                }
+                if let Some(uvs) = uvs {
+                    tri = tri.with_uvs(*uvs);
+                }
+                tri.wrap()
+            }
+            ObjectDescription::Mesh { path, material } => {
+                let material = ctx.material(material, &location)?;
+                match TriangleMesh::from_obj(ctx.base_dir.join(path), material) {
+                    Ok(mesh) => mesh.wrap(),
+                    Err(e) => {
+                        return Err(SceneFileError::Invalid {
+                            location,
+                            message: format!("failed to load mesh {:?}: {}", path, e),
+                        })
+                    }
+                }
+            }
+            ObjectDescription::Heightfield {
+                source,
+                min,
+                size,
+                material,
+            } => {
+                let material = ctx.material(material, &location)?;
+                let field = match source {
+                    HeightfieldSource::Image { path } => {
+                        Heightfield::from_image(ctx.base_dir.join(path), *min, *size, material)
+                    }
+                    HeightfieldSource::Perlin { resolution, scale } => Heightfield::from_noise(
+                        &::noise::Perlin::default(),
+                        *scale,
+                        *resolution,
+                        *min,
+                        *size,
+                        material,
+                    ),
+                };
+                match field {
+                    Ok(field) => field.wrap(),
+                    Err(e) => {
+                        return Err(SceneFileError::Invalid {
+                            location,
+                            message: format!("failed to create heightfield: {}", e),
+                        })
+                    }
+                }
+            }
+            ObjectDescription::Transform {
+                object,
+                scale,
+                rotation: (axis, degrees),
+                translation,
+                operations,
+            } => {
+                if *degrees != 0.0 && axis.length_squared() == 0.0 {
+                    return Err(SceneFileError::Invalid {
+                        location,
+                        message: "rotation axis must not be zero".to_string(),
+                    });
+                }
+
+                let object = object.build(ctx, format!("{}.object", location))?;
+                let mut transform = Transform::new(&object);
+                transform.with_scale_factor(*scale);
+                if *degrees != 0.0 {
+                    transform.with_axis_angle(axis.normalize(), degrees.to_radians());
+                }
+                transform.with_translation(*translation);
+                for operation in check_operations(operations, &location)? {
+                    transform.with_operation(operation);
+                }
+                transform.finalize().wrap()
+            }
+            ObjectDescription::Instance {
+                prototype,
+                operations,
+                material,
+            } => {
+                let mut instance = Instance::new(ctx.prototype(prototype, &location)?);
+                for operation in check_operations(operations, &location)? {
+                    instance = instance.with_operation(operation);
+                }
+                if let Some(material) = material {
+                    instance = instance.with_material(ctx.material(material, &location)?);
+                }
+                instance.wrap()
+            }
+            ObjectDescription::AnimatedTransform { object, keyframes } => {
+                let object = object.build(ctx, format!("{}.object", location))?;
+                // rotations need a unit axis
+                if let Some(idx) = keyframes
+                    .iter()
+                    .position(|k| k.axis.length_squared() == 0.0)
+                {
+                    return Err(SceneFileError::Invalid {
+                        location: format!("{}.keyframes[{}]", location, idx),
+                        message: "rotation axis must not be zero".to_string(),
+                    });
+                }
+                AnimatedTransform::new(&object, keyframes.clone()).wrap()
+            }
+            ObjectDescription::Sdf {
+                sdf,
+                min,
+                max,
+                max_steps,
+                epsilon,
+                material,
+            } => SdfObject::new(
+                &sdf.build(),
+                BoundingBox::new(*min, *max),
+                ctx.material(material, &location)?,
+            )
+            .with_max_steps(*max_steps)
+            .with_epsilon(*epsilon)
+            .wrap(),
+            ObjectDescription::Csg {
+                operation,
+                left,
+                right,
+            } => {
+                let left = left.build(ctx, format!("{}.left", location))?;
+                let right = right.build(ctx, format!("{}.right", location))?;
+                Csg::new(*operation, &left, &right).wrap()
+            }
+            ObjectDescription::ConstantMedium {
+                boundary,
+                albedo,
+                density,
+            } => {
+                let boundary = boundary.build(ctx, format!("{}.boundary", location))?;
+                let albedo = albedo.build(ctx.base_dir, &format!("{}.albedo", location))?;
+                ConstantMedium::new(&boundary, &albedo, *density).wrap()
+            }
+            ObjectDescription::NonConstantMedium {
+                boundary,
+                albedo,
+                scale,
+            } => {
+                let boundary = boundary.build(ctx, format!("{}.boundary", location))?;
+                let albedo = albedo.build(ctx.base_dir, &format!("{}.albedo", location))?;
+                NonConstantMedium::new(&boundary, &albedo, ::noise::Perlin::default(), *scale)
+                    .wrap()
+            }
+            ObjectDescription::List { objects } => Self::build_all(objects, ctx, &location)?.wrap(),
+            ObjectDescription::Bvh { objects } => {
+                let objects = Self::build_all(objects, ctx, &location)?;
+                match LinearBvh::new(objects, ctx.time0, ctx.time1) {
+                    Ok(bvh) => bvh.wrap(),
+                    Err(e) => {
+                        return Err(SceneFileError::Invalid {
+                            location,
+                            message: e.to_string(),
+                        })
+                    }
+                }
+            }
+        };
+
+        Ok(obj)
+    }
+
+    /// Returns whether the object is, or holds, an [Instance]
+    fn has_instances(&self) -> bool {
+        match self {
+            ObjectDescription::Instance { .. } => true,
+            ObjectDescription::Transform { object, .. }
+            | ObjectDescription::AnimatedTransform { object, .. } => object.has_instances(),
+            ObjectDescription::Csg { left, right, .. } => {
+                left.has_instances() || right.has_instances()
+            }
+            ObjectDescription::ConstantMedium { boundary, .. }
+            | ObjectDescription::NonConstantMedium { boundary, .. } => boundary.has_instances(),
+            ObjectDescription::List { objects } | ObjectDescription::Bvh { objects } => {
+                objects.iter().any(Self::has_instances)
+            }
+            _ => false,
+        }
+    }
+
+    /// Creates each of the described objects
+    fn build_all(
+        objects: &[ObjectDescription],
+        ctx: &BuildContext,
+        location: &str,
+    ) -> Result<HittableList, SceneFileError> {
+        objects
+            .iter()
+            .enumerate()
+            .map(|(idx, obj)| obj.build(ctx, format!("{}.objects[{}]", location, idx)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{ray::Ray, scenes::SceneType};
+
+    #[test]
+    fn load_example_scene() {
+        let path = Path::new("resources/scenes/cornell_box.ron");
+        let (_, world, dimensions) = load_scene_file(path, 600).unwrap();
+        assert_eq!(world.len(), 8);
+        assert_eq!(dimensions, UVec2::new(600, 600));
+    }
+
+    #[test]
+    fn describe_builtin_scene() {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+        let (cam, world, _) = crate::scenes::get_scene(600, SceneType::CornellBox, &mut rng);
+        let desc = describe_scene(&cam, &world, Path::new("")).unwrap();
+        assert_eq!(desc.objects.len(), world.len());
+
+        // the written description should read back into the same description
+        let contents = ron::to_string(&desc).unwrap();
+        let read_back: SceneDescription = ron::from_str(&contents).unwrap();
+        assert_eq!(ron::to_string(&read_back).unwrap(), contents);
+    }
+
+    #[test]
+    fn describe_sdf_scene() {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+        let (cam, world, _) = crate::scenes::get_scene(600, SceneType::SdfShapes, &mut rng);
+        let desc = describe_scene(&cam, &world, Path::new("")).unwrap();
+
+        // reading the description back builds the same shapes
+        let contents = ron::to_string(&desc).unwrap();
+        let read_back: SceneDescription = ron::from_str(&contents).unwrap();
+        let (_, read_world, _) = read_back.build(600, Path::new("")).unwrap();
+        assert_eq!(read_world.len(), world.len());
+        let ray = Ray::new(Vec3A::new(0.0, 1.0, 8.0), -Vec3A::Z, 0.0);
+        let hits = |world: &HittableList| -> Vec<Option<f32>> {
+            world
+                .iter()
+                .map(|obj| obj.hit(&ray, 0.001, f32::INFINITY).map(|rec| rec.t))
+                .collect()
+        };
+        assert!(hits(&world).iter().any(Option::is_some));
+        assert_eq!(hits(&read_world), hits(&world));
+
+        // and describes them the same way again
+        let desc = describe_scene(&cam, &read_world, Path::new("")).unwrap();
+        assert_eq!(ron::to_string(&desc).unwrap(), contents);
+    }
+
+    #[test]
+    fn describe_paths_relative_to_scene_file() {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+        let (cam, world, _) = crate::scenes::get_scene(600, SceneType::Earth, &mut rng);
+        let base_dir = Path::new("resources/scenes");
+        let desc = describe_scene(&cam, &world, base_dir).unwrap();
+        match &desc.materials["material0"] {
+            MaterialDescription::Lambertian {
+                albedo: TextureDescription::Image { path },
+            } => assert_eq!(path, Path::new("../earthmap.jpg")),
+            desc => panic!("expected an image texture, got {:?}", desc),
+        }
+
+        // loading the description and describing it again gives the same text
+        let contents = ron::to_string(&desc).unwrap();
+        let (cam, world, _) = desc.build(600, base_dir).unwrap();
+        let desc = describe_scene(&cam, &world, base_dir).unwrap();
+        assert_eq!(ron::to_string(&desc).unwrap(), contents);
+    }
+
+    #[test]
+    fn parse_error_points_at_line() {
+        let contents =
+            "(\n    objects: [\n        Sphere(center: (0, 0, 0), radious: 1.0),\n    ],\n)";
+        let err = SceneFileError::from(ron::from_str::<SceneDescription>(contents).unwrap_err());
+        match err {
+            SceneFileError::Parse { line, .. } => assert_eq!(line, 3),
+            _ => panic!("expected a parse error, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn missing_image_points_at_texture() {
+        let contents = r#"(
+            materials: {
+                "checks": Lambertian(
+                    albedo: Checkered(odd: SolidColor((1, 1, 1)), even: Image(path: "missing.png")),
+                ),
+            },
+            objects: [],
+        )"#;
+        let description: SceneDescription = ron::from_str(contents).unwrap();
+        match description.build(600, Path::new("resources")) {
+            Err(SceneFileError::Invalid { location, .. }) => {
+                assert_eq!(location, r#"materials["checks"].albedo.even"#)
+            }
+            result => panic!("expected an invalid texture, got {:?}", result.err()),
+        }
+    }
+}
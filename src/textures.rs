@@ -7,7 +7,10 @@ use std::fmt::Debug;
 use glam::Vec3A;
 
 pub use crate::color::Color as SolidColor;
-use crate::color::Color;
+use crate::{
+    color::Color,
+    scenes::{MaterialNames, TextureDescription},
+};
 
 pub use self::image::*;
 pub use self::noise::*;
@@ -21,6 +24,14 @@ pub mod noise;
 pub trait Texture: Send + Sync {
     /// Returns the color value at the uv coordinates or point for the texture
     fn color(&self, u: f32, v: f32, point: Vec3A) -> Color;
+
+    /// Returns a description of the texture, as used by scene files
+    ///
+    /// File paths are written through `names`.
+    /// Returns `None` if the texture can't be described, which is the default.
+    fn describe(&self, _names: &MaterialNames) -> Option<TextureDescription> {
+        None
+    }
 }
 
 impl Debug for dyn Texture {
@@ -38,4 +49,8 @@ impl Texture for SolidColor {
         // Solid Color is the same at all coordinates
         *self
     }
+
+    fn describe(&self, _names: &MaterialNames) -> Option<TextureDescription> {
+        Some(TextureDescription::SolidColor(*self))
+    }
 }
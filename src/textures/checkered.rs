@@ -4,7 +4,10 @@ use std::sync::Arc;
 
 use glam::Vec3A;
 
-use crate::color::Color;
+use crate::{
+    color::Color,
+    scenes::{MaterialNames, TextureDescription},
+};
 
 use super::Texture;
 
@@ -36,4 +39,11 @@ impl Texture for Checkered {
             self.even.color(u, v, point)
         }
     }
+
+    fn describe(&self, names: &MaterialNames) -> Option<TextureDescription> {
+        Some(TextureDescription::Checkered {
+            odd: Box::new(self.odd.describe(names)?),
+            even: Box::new(self.even.describe(names)?),
+        })
+    }
 }
@@ -4,7 +4,10 @@ use std::path::PathBuf;
 
 use glam::Vec3A;
 
-use crate::color::Color;
+use crate::{
+    color::Color,
+    scenes::{MaterialNames, TextureDescription},
+};
 
 use super::Texture;
 
@@ -13,6 +16,8 @@ use super::Texture;
 pub struct ImageMap {
     /// The image buffer used as the texture
     image: Option<image::RgbImage>,
+    /// The path the image was loaded from
+    file_path: PathBuf,
 }
 
 impl ImageMap {
@@ -22,7 +27,7 @@ impl ImageMap {
     /// * if successful, holds the decoded [image::RgbImage] in an Option
     /// * on error, holds `None`
     pub fn new(file_path: PathBuf) -> Self {
-        let img_result = image::open(file_path);
+        let img_result = image::open(&file_path);
 
         let img = match img_result {
             Ok(dyn_img) => Some(dyn_img.to_rgb8()),
@@ -32,7 +37,19 @@ impl ImageMap {
             }
         };
 
-        Self { image: img }
+        Self {
+            image: img,
+            file_path,
+        }
+    }
+
+    /// Loads a new [ImageMap] from the image located at `file_path`, failing if it can't be loaded
+    pub fn load(file_path: PathBuf) -> Result<Self, image::ImageError> {
+        let image = image::open(&file_path)?.to_rgb8();
+        Ok(Self {
+            image: Some(image),
+            file_path,
+        })
     }
 }
 
@@ -56,4 +73,10 @@ impl Texture for ImageMap {
             }
         }
     }
+
+    fn describe(&self, names: &MaterialNames) -> Option<TextureDescription> {
+        Some(TextureDescription::Image {
+            path: names.path(&self.file_path),
+        })
+    }
 }
@@ -4,12 +4,15 @@ use glam::Vec3A;
 use noise::NoiseFn;
 
 use super::Texture;
-use crate::color::Color;
+use crate::{
+    color::Color,
+    scenes::{is_default_perlin, MaterialNames, TextureDescription},
+};
 
 #[derive(Debug)]
 pub struct NoiseTexture<N>
 where
-    N: NoiseFn<[f64; 3]> + Send + Sync,
+    N: NoiseFn<f64, 3> + Send + Sync,
 {
     noise: N,
     scale: f32,
@@ -17,7 +20,7 @@ where
 
 impl<N> NoiseTexture<N>
 where
-    N: NoiseFn<[f64; 3]> + Send + Sync,
+    N: NoiseFn<f64, 3> + Send + Sync,
 {
     pub fn new(noise: N, scale: f32) -> Self {
         Self { noise, scale }
@@ -26,11 +29,20 @@ where
 
 impl<N> Texture for NoiseTexture<N>
 where
-    N: NoiseFn<[f64; 3]> + Send + Sync,
+    N: NoiseFn<f64, 3> + Send + Sync + 'static,
 {
     fn color(&self, _u: f32, _v: f32, point: glam::Vec3A) -> super::SolidColor {
         let noise_val = self.noise.get((self.scale * point).as_dvec3().to_array());
         let normalized_noise = 0.5 * (noise_val + 1.0);
         Color::new(Vec3A::splat(normalized_noise as f32))
     }
+
+    fn describe(&self, _names: &MaterialNames) -> Option<TextureDescription> {
+        // only Perlin noise can be described
+        if !is_default_perlin(&self.noise) {
+            return None;
+        }
+
+        Some(TextureDescription::Perlin { scale: self.scale })
+    }
 }
@@ -2,5 +2,7 @@
 //!
 //! External create wrappers, small functions, etc.
 
+pub mod geometry;
+pub mod polynomial;
 pub mod progress;
 pub mod random;
@@ -0,0 +1,27 @@
+//! Miscelleanous geometric utilities shared between hittables
+
+use glam::Vec3A;
+
+/// Returns two unit vectors that, together with the unit `normal`, form an orthonormal basis
+pub fn tangent_axes(normal: Vec3A) -> (Vec3A, Vec3A) {
+    // any vector not parallel to the normal works, prefer the X axis for u
+    let helper = if normal.x.abs() > 0.9 {
+        Vec3A::Y
+    } else {
+        Vec3A::X
+    };
+    let u_axis = (helper - normal * helper.dot(normal)).normalize();
+    let v_axis = normal.cross(u_axis);
+    (u_axis, v_axis)
+}
+
+/// Returns the extent along each axis of a circle with a radius of 1, facing towards the unit `normal`.
+///
+/// The extent shrinks as the normal aligns with an axis.
+pub fn circle_extent(normal: Vec3A) -> Vec3A {
+    let extent = (Vec3A::ONE - normal * normal)
+        .max(Vec3A::ZERO)
+        .to_array()
+        .map(f32::sqrt);
+    Vec3A::from(extent)
+}
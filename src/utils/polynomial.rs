@@ -0,0 +1,185 @@
+//! Real root finding for low degree polynomials
+//!
+//! Based on Jochen Schwarze's solvers from Graphics Gems I ("Cubic and Quartic Roots"),
+//! with the quartic roots refined by Newton's method afterwards.
+
+use std::{f64::consts::PI, ops::Deref};
+
+/// Threshold below which a value is considered zero
+const EPSILON: f64 = 1e-9;
+
+/// Number of Newton's method iterations used to refine quartic roots
+const NEWTON_ITERATIONS: usize = 3;
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < EPSILON
+}
+
+/// Up to four real roots of a polynomial, in ascending order
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Roots {
+    values: [f64; 4],
+    len: usize,
+}
+
+impl Roots {
+    fn push(&mut self, root: f64) {
+        self.values[self.len] = root;
+        self.len += 1;
+    }
+
+    /// Shifts every root by `offset`
+    fn shift(mut self, offset: f64) -> Self {
+        self.values[..self.len]
+            .iter_mut()
+            .for_each(|root| *root += offset);
+        self
+    }
+}
+
+impl Deref for Roots {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        &self.values[..self.len]
+    }
+}
+
+/// Returns the real roots of `x^2 + b * x + c`
+pub fn solve_quadratic(b: f64, c: f64) -> Roots {
+    let mut roots = Roots::default();
+    let p = b / 2.0;
+    let discrim = p * p - c;
+
+    if is_zero(discrim) {
+        roots.push(-p);
+    } else if discrim > 0.0 {
+        let sqrt_d = discrim.sqrt();
+        roots.push(-sqrt_d - p);
+        roots.push(sqrt_d - p);
+    }
+    roots
+}
+
+/// Returns the real roots of `x^3 + b * x^2 + c * x + d`
+pub fn solve_cubic(b: f64, c: f64, d: f64) -> Roots {
+    let mut roots = Roots::default();
+
+    // substitute x = y - b/3 to eliminate the quadratic term: y^3 + 3p * y + 2q = 0
+    let sq_b = b * b;
+    let p = (-sq_b / 3.0 + c) / 3.0;
+    let q = (2.0 / 27.0 * b * sq_b - b * c / 3.0 + d) / 2.0;
+
+    let cb_p = p * p * p;
+    let discrim = q * q + cb_p;
+
+    if is_zero(discrim) {
+        if is_zero(q) {
+            // one triple root
+            roots.push(0.0);
+        } else {
+            // one single and one double root
+            let u = (-q).cbrt();
+            roots.push(-u);
+            roots.push(2.0 * u);
+        }
+    } else if discrim < 0.0 {
+        // three real roots, found with trigonometry
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        roots.push(t * phi.cos());
+        roots.push(-t * (phi + PI / 3.0).cos());
+        roots.push(-t * (phi - PI / 3.0).cos());
+    } else {
+        // one real root
+        let sqrt_d = discrim.sqrt();
+        let u = (sqrt_d - q).cbrt();
+        let v = -(sqrt_d + q).cbrt();
+        roots.push(u + v);
+    }
+
+    roots.values[..roots.len].sort_by(f64::total_cmp);
+    roots.shift(-b / 3.0)
+}
+
+/// Returns the real roots of `x^4 + b * x^3 + c * x^2 + d * x + e`
+pub fn solve_quartic(b: f64, c: f64, d: f64, e: f64) -> Roots {
+    // substitute x = y - b/4 to eliminate the cubic term: y^4 + p * y^2 + q * y + r = 0
+    let sq_b = b * b;
+    let p = -3.0 / 8.0 * sq_b + c;
+    let q = sq_b * b / 8.0 - b * c / 2.0 + d;
+    let r = -3.0 / 256.0 * sq_b * sq_b + sq_b * c / 16.0 - b * d / 4.0 + e;
+
+    let mut roots = Roots::default();
+    if is_zero(r) {
+        // no absolute term: y * (y^3 + p * y + q) = 0
+        roots = solve_cubic(0.0, p, q);
+        roots.push(0.0);
+    } else {
+        // use the largest root of the resolvent cubic, which is the most stable choice
+        let resolvent = solve_cubic(-p / 2.0, -r, r * p / 2.0 - q * q / 8.0);
+        let z = resolvent[resolvent.len() - 1];
+
+        // split into two quadratics
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if is_zero(u) {
+            0.0
+        } else if u > 0.0 {
+            u.sqrt()
+        } else {
+            return roots;
+        };
+        let v = if is_zero(v) {
+            0.0
+        } else if v > 0.0 {
+            v.sqrt()
+        } else {
+            return roots;
+        };
+
+        let v = if q < 0.0 { -v } else { v };
+        for root in solve_quadratic(v, z - u)
+            .iter()
+            .chain(solve_quadratic(-v, z + u).iter())
+        {
+            roots.push(*root);
+        }
+    }
+
+    // undo the substitution, then refine each root on the original polynomial
+    let mut roots = roots.shift(-b / 4.0);
+    for root in roots.values[..roots.len].iter_mut() {
+        for _ in 0..NEWTON_ITERATIONS {
+            let x = *root;
+            let f = (((x + b) * x + c) * x + d) * x + e;
+            let df = ((4.0 * x + 3.0 * b) * x + 2.0 * c) * x + d;
+            if is_zero(df) {
+                break;
+            }
+            *root = x - f / df;
+        }
+    }
+
+    roots.values[..roots.len].sort_by(f64::total_cmp);
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quartic_roots() {
+        // (x - 1)(x + 2)(x - 3)(x - 0.5)
+        let roots = solve_quartic(-2.5, -4.0, 8.5, -3.0);
+        let expected = [-2.0, 0.5, 1.0, 3.0];
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-9, "{} != {}", root, expected);
+        }
+
+        // x^4 + 1 has no real roots
+        assert!(solve_quartic(0.0, 0.0, 0.0, 1.0).is_empty());
+    }
+}